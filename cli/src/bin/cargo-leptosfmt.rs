@@ -0,0 +1,279 @@
+use std::{
+    collections::BTreeSet,
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+use anyhow::{bail, Context};
+use clap::Parser;
+use serde::Deserialize;
+
+/// Exit code used when the workspace could not be inspected, matches the CLI's error exit code
+const EXIT_ERROR: i32 = 2;
+
+#[derive(Parser, Debug)]
+#[command(bin_name = "cargo")]
+enum Cargo {
+    /// Format the view macros of the packages in a Cargo workspace
+    #[command(author, version)]
+    Leptosfmt(Args),
+}
+
+#[derive(clap::Args, Debug)]
+struct Args {
+    /// Package to format, can be repeated
+    #[arg(short, long = "package", value_name = "SPEC")]
+    packages: Vec<String>,
+
+    /// Format all packages in the workspace
+    #[arg(long, conflicts_with = "packages")]
+    all: bool,
+
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH")]
+    manifest_path: Option<PathBuf>,
+
+    /// Options passed on to leptosfmt, e.g. `cargo leptosfmt -- --check`
+    #[arg(last = true)]
+    leptosfmt_args: Vec<OsString>,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    workspace_members: Vec<String>,
+    workspace_root: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    manifest_path: PathBuf,
+    targets: Vec<Target>,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    kind: Vec<String>,
+    src_path: PathBuf,
+}
+
+fn main() {
+    let Cargo::Leptosfmt(args) = Cargo::parse();
+
+    let dirs = match source_dirs(&args) {
+        Ok(dirs) => dirs,
+        Err(err) => {
+            eprintln!("{:#}", err);
+            process::exit(EXIT_ERROR);
+        }
+    };
+
+    if dirs.is_empty() {
+        return;
+    }
+
+    let leptosfmt = leptosfmt_path();
+    let status = Command::new(&leptosfmt)
+        .args(&args.leptosfmt_args)
+        .args(&dirs)
+        .status();
+
+    match status {
+        Ok(status) => process::exit(status.code().unwrap_or(EXIT_ERROR)),
+        Err(err) => {
+            eprintln!("could not run {}: {}", leptosfmt.display(), err);
+            process::exit(EXIT_ERROR);
+        }
+    }
+}
+
+/// Collects the directories containing the sources of the selected packages.
+///
+/// Build scripts and targets outside of the workspace are skipped.
+fn source_dirs(args: &Args) -> anyhow::Result<BTreeSet<PathBuf>> {
+    let metadata = cargo_metadata(args.manifest_path.as_deref())?;
+
+    let members: Vec<_> = metadata
+        .packages
+        .iter()
+        .filter(|package| metadata.workspace_members.contains(&package.id))
+        .collect();
+
+    let selected: Vec<_> = if args.all {
+        members
+    } else if !args.packages.is_empty() {
+        if let Some(unknown) = args
+            .packages
+            .iter()
+            .find(|name| !members.iter().any(|package| &&package.name == name))
+        {
+            bail!("package `{unknown}` is not a member of the workspace");
+        }
+
+        members
+            .into_iter()
+            .filter(|package| args.packages.contains(&package.name))
+            .collect()
+    } else {
+        current_packages(
+            members,
+            &metadata.workspace_root,
+            args.manifest_path.as_deref(),
+        )?
+    };
+
+    Ok(selected
+        .iter()
+        .flat_map(|package| &package.targets)
+        .filter(|target| !target.kind.iter().any(|kind| kind == "custom-build"))
+        .filter(|target| target.src_path.starts_with(&metadata.workspace_root))
+        .filter_map(|target| target.src_path.parent())
+        .map(Path::to_owned)
+        .collect())
+}
+
+/// Selects the package of the manifest in use, or all members for the manifest of the workspace root, like `cargo fmt`
+fn current_packages<'a>(
+    members: Vec<&'a Package>,
+    workspace_root: &Path,
+    manifest_path: Option<&Path>,
+) -> anyhow::Result<Vec<&'a Package>> {
+    let manifest_path = match manifest_path {
+        Some(path) => env::current_dir()?.join(path),
+        None => locate_manifest()?,
+    };
+    // cargo metadata returns canonical paths
+    let manifest_path = canonicalize(&manifest_path)?;
+
+    if let Some(package) = members
+        .iter()
+        .find(|package| package.manifest_path == manifest_path)
+    {
+        return Ok(vec![*package]);
+    }
+
+    if manifest_path != canonicalize(&workspace_root.join("Cargo.toml"))? {
+        bail!(
+            "{} is neither the manifest of a workspace member nor of the workspace root",
+            manifest_path.display()
+        );
+    }
+    Ok(members)
+}
+
+fn canonicalize(path: &Path) -> anyhow::Result<PathBuf> {
+    fs::canonicalize(path).with_context(|| format!("could not find {}", path.display()))
+}
+
+fn cargo_metadata(manifest_path: Option<&Path>) -> anyhow::Result<Metadata> {
+    let mut command = cargo();
+    command.args(["metadata", "--format-version", "1", "--no-deps"]);
+    if let Some(manifest_path) = manifest_path {
+        command.arg("--manifest-path").arg(manifest_path);
+    }
+
+    let output = command.output().context("could not run cargo metadata")?;
+    if !output.status.success() {
+        bail!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+fn locate_manifest() -> anyhow::Result<PathBuf> {
+    #[derive(Deserialize)]
+    struct Location {
+        root: PathBuf,
+    }
+
+    let output = cargo()
+        .args(["locate-project", "--message-format", "json"])
+        .output()
+        .context("could not run cargo locate-project")?;
+    if !output.status.success() {
+        bail!(
+            "could not find Cargo.toml: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let location: Location = serde_json::from_slice(&output.stdout)?;
+    Ok(location.root)
+}
+
+fn cargo() -> Command {
+    Command::new(env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo")))
+}
+
+/// The leptosfmt binary installed next to this one, falling back to the one in `PATH`
+fn leptosfmt_path() -> PathBuf {
+    if let Some(path) = env::var_os("LEPTOSFMT") {
+        return path.into();
+    }
+
+    let name = format!("leptosfmt{}", env::consts::EXE_SUFFIX);
+    env::current_exe()
+        .ok()
+        .map(|exe| exe.with_file_name(&name))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| name.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, manifest_path: PathBuf) -> Package {
+        Package {
+            id: name.to_owned(),
+            name: name.to_owned(),
+            manifest_path,
+            targets: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn current_package() {
+        let root = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(root.path()).unwrap();
+        for manifest in [
+            "Cargo.toml",
+            "app/Cargo.toml",
+            "lib/Cargo.toml",
+            "other/Cargo.toml",
+        ] {
+            let path = root.join(manifest);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let app = package("app", root.join("app/Cargo.toml"));
+        let lib = package("lib", root.join("lib/Cargo.toml"));
+        let members = || vec![&app, &lib];
+        let names = |packages: Vec<&Package>| -> Vec<String> {
+            packages
+                .iter()
+                .map(|package| package.name.clone())
+                .collect()
+        };
+
+        // a path that isn't canonical, like `--manifest-path ../lib/Cargo.toml`
+        let lib_manifest = root.join("app/../lib/Cargo.toml");
+        let selected = current_packages(members(), &root, Some(&lib_manifest)).unwrap();
+        assert_eq!(names(selected), ["lib"]);
+
+        let selected = current_packages(members(), &root, Some(&root.join("Cargo.toml"))).unwrap();
+        assert_eq!(names(selected), ["app", "lib"]);
+
+        let other_manifest = root.join("other/Cargo.toml");
+        assert!(current_packages(members(), &root, Some(&other_manifest)).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex,
+};
+
+use leptosfmt_formatter::FormatterSettings;
+
+const CACHE_FILE: &str = "formatted";
+
+/// Remembers which files were already formatted, so they can be skipped without parsing them.
+///
+/// Every file is stored with a hash of its content, its settings and the version of leptosfmt.
+/// Only files that came out of the formatter unchanged are stored.
+pub struct Cache {
+    dir: PathBuf,
+    entries: HashMap<PathBuf, u64>,
+    updates: Mutex<HashMap<PathBuf, u64>>,
+}
+
+impl Cache {
+    pub fn open(dir: PathBuf) -> Self {
+        let entries = fs::read_to_string(dir.join(CACHE_FILE))
+            .map(|cache| parse_entries(&cache))
+            .unwrap_or_default();
+
+        Self {
+            dir,
+            entries,
+            updates: Mutex::default(),
+        }
+    }
+
+    /// The cache directory of the Cargo project containing the current directory, if any
+    pub fn default_dir() -> Option<PathBuf> {
+        if let Some(target_dir) = env::var_os("CARGO_TARGET_DIR") {
+            return Some(PathBuf::from(target_dir).join("leptosfmt-cache"));
+        }
+
+        let project_root = project_root(&env::current_dir().ok()?)?;
+        Some(project_root.join("target").join("leptosfmt-cache"))
+    }
+
+    pub fn key(&self, source: &str, settings: FormatterSettings) -> u64 {
+        let settings = serde_json::to_string(&settings).unwrap();
+
+        let mut hasher = Fnv1a::new();
+        for field in [env!("CARGO_PKG_VERSION"), &settings, source] {
+            hasher.write_field(field.as_bytes());
+        }
+        hasher.0
+    }
+
+    pub fn is_formatted(&self, path: &Path, key: u64) -> bool {
+        fs::canonicalize(path).is_ok_and(|path| self.entries.get(&path) == Some(&key))
+    }
+
+    pub fn insert(&self, path: &Path, key: u64) {
+        if let Ok(path) = fs::canonicalize(path) {
+            self.updates.lock().unwrap().insert(path, key);
+        }
+    }
+
+    pub fn save(self) -> io::Result<()> {
+        let updates = self.updates.into_inner().unwrap();
+        if updates.is_empty() {
+            return Ok(());
+        }
+
+        let mut entries = self.entries;
+        entries.extend(updates);
+
+        let cache: String = entries
+            .iter()
+            .filter(|(path, _)| path.is_file())
+            .map(|(path, key)| format!("{:016x} {}\n", key, path.display()))
+            .collect();
+
+        fs::create_dir_all(&self.dir)?;
+        let temp_path = self
+            .dir
+            .join(format!("{}.{}.tmp", CACHE_FILE, process::id()));
+        fs::write(&temp_path, cache)?;
+        fs::rename(temp_path, self.dir.join(CACHE_FILE))
+    }
+}
+
+/// The directory of the closest Cargo manifest with a `[workspace]`, or else of the closest package,
+/// which is where cargo puts the `target` directory
+fn project_root(dir: &Path) -> Option<PathBuf> {
+    let manifests: Vec<_> = dir
+        .ancestors()
+        .filter(|dir| dir.join("Cargo.toml").is_file())
+        .collect();
+    let is_workspace = |dir: &Path| {
+        fs::read_to_string(dir.join("Cargo.toml"))
+            .ok()
+            .and_then(|manifest| manifest.parse::<toml::Table>().ok())
+            .is_some_and(|manifest| manifest.contains_key("workspace"))
+    };
+
+    let root = manifests
+        .iter()
+        .find(|dir| is_workspace(dir))
+        .or(manifests.first())?;
+    Some(root.to_path_buf())
+}
+
+/// 64-bit FNV-1a, used instead of `DefaultHasher` as the hashes are stored on disk,
+/// and the algorithm of `DefaultHasher` may change between releases of Rust
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Writes the length before the bytes, so moving bytes between fields changes the hash
+    fn write_field(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+}
+
+fn parse_entries(cache: &str) -> HashMap<PathBuf, u64> {
+    cache
+        .lines()
+        .filter_map(|line| {
+            let (key, path) = line.split_once(' ')?;
+            Some((PathBuf::from(path), u64::from_str_radix(key, 16).ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fnv1a(bytes: &[u8]) -> u64 {
+        let mut hasher = Fnv1a::new();
+        hasher.write(bytes);
+        hasher.0
+    }
+
+    #[test]
+    fn fnv1a_test_vectors() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn key_depends_on_source_and_settings() {
+        let cache = Cache::open(PathBuf::from("unused"));
+        let settings = FormatterSettings::default();
+        let key = cache.key("fn main() {}", settings);
+
+        assert_eq!(key, cache.key("fn main() {}", settings));
+        assert_ne!(key, cache.key("fn main() { }", settings));
+        let settings = FormatterSettings {
+            max_width: 120,
+            ..settings
+        };
+        assert_ne!(key, cache.key("fn main() {}", settings));
+    }
+
+    #[test]
+    fn project_root_of_workspace_or_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let manifest = |path: &str, contents: &str| {
+            fs::create_dir_all(dir.join(path)).unwrap();
+            fs::write(dir.join(path).join("Cargo.toml"), contents).unwrap();
+        };
+        // an unrelated project the others happen to be in
+        manifest("", "[package]\nname = \"outer\"");
+        manifest("project/app", "[package]\nname = \"app\"");
+        fs::create_dir_all(dir.join("project/app/src")).unwrap();
+
+        let app = dir.join("project/app");
+        assert_eq!(project_root(&app.join("src")), Some(app.clone()));
+
+        manifest("project", "[workspace]\nmembers = [\"app\"]");
+        let project = dir.join("project");
+        assert_eq!(project_root(&app.join("src")), Some(project.clone()));
+        assert_eq!(project_root(&project), Some(project.clone()));
+        assert_eq!(project_root(dir), Some(dir.to_owned()));
+    }
+
+    #[test]
+    fn parse_entries_round_trip() {
+        let entries = parse_entries(
+            "00000000000000ff /project/src/main.rs\n\
+             0123456789abcdef /project/src/with space.rs\n\
+             invalid line\n",
+        );
+        assert_eq!(
+            entries,
+            HashMap::from([
+                (PathBuf::from("/project/src/main.rs"), 0xff),
+                (
+                    PathBuf::from("/project/src/with space.rs"),
+                    0x0123_4567_89ab_cdef
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn save_merges_and_prunes_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let [a, b, c] = ["a.rs", "b.rs", "c.rs"].map(|name| {
+            let path = dir.path().join(name);
+            fs::write(&path, "").unwrap();
+            path
+        });
+
+        let cache = Cache::open(cache_dir.clone());
+        cache.insert(&a, 1);
+        cache.insert(&b, 2);
+        cache.save().unwrap();
+
+        let cache = Cache::open(cache_dir.clone());
+        assert!(cache.is_formatted(&a, 1));
+        assert!(cache.is_formatted(&b, 2));
+        assert!(!cache.is_formatted(&b, 3));
+        assert!(!cache.is_formatted(&c, 3));
+
+        // the entries of the previous run are kept, unless their file was deleted
+        fs::remove_file(&a).unwrap();
+        cache.insert(&c, 3);
+        cache.save().unwrap();
+
+        let entries = parse_entries(&fs::read_to_string(cache_dir.join(CACHE_FILE)).unwrap());
+        assert_eq!(
+            entries,
+            HashMap::from([
+                (fs::canonicalize(&b).unwrap(), 2),
+                (fs::canonicalize(&c).unwrap(), 3),
+            ])
+        );
+    }
+}
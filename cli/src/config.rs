@@ -0,0 +1,742 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, bail, Context};
+use leptosfmt_formatter::FormatterSettings;
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+use crate::{editorconfig::EditorConfig, input, parent_dir};
+
+/// Name of the config files that are discovered in the parent directories
+pub const CONFIG_FILE: &str = "leptosfmt.toml";
+
+/// Prefix of the environment variables that override a setting, e.g. `LEPTOSFMT_MAX_WIDTH`
+const ENV_PREFIX: &str = "LEPTOSFMT_";
+
+/// Names of the config files of rustfmt, in the order rustfmt looks for them in every directory
+const RUSTFMT_CONFIG_FILES: [&str; 2] = [".rustfmt.toml", "rustfmt.toml"];
+
+/// Settings of rustfmt that leptosfmt shares
+const RUSTFMT_SETTINGS: [&str; 2] = ["max_width", "tab_spaces"];
+
+/// The values that are accepted for the numeric settings
+pub const RANGES: [(&str, RangeInclusive<i64>); 2] =
+    [("max_width", 10..=1000), ("tab_spaces", 1..=16)];
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Config {
+    #[serde(flatten)]
+    pub settings: FormatterSettings,
+
+    /// Gitignore-style patterns of files to skip, relative to the config file
+    #[serde(default)]
+    pub ignore: Vec<String>,
+
+    /// Directory of the config file that sets `ignore`
+    #[serde(skip)]
+    pub dir: Option<PathBuf>,
+}
+
+impl Config {
+    /// The keys that can be set in the config file
+    pub fn keys() -> Vec<String> {
+        Self::defaults().into_iter().map(|(key, _)| key).collect()
+    }
+
+    /// The default value of every key
+    fn defaults() -> Table {
+        match Value::try_from(Config::default()) {
+            Ok(Value::Table(table)) => table,
+            _ => Table::new(),
+        }
+    }
+}
+
+/// Settings read from one source, e.g. a config file
+struct Layer {
+    /// Where the settings come from, e.g. the path of a config file
+    source: String,
+    /// The directory that `ignore` is relative to
+    dir: Option<PathBuf>,
+    table: Table,
+}
+
+/// Resolves the config of a directory by merging all config files from the outermost to the one
+/// in the directory itself, so the innermost value of every setting wins.
+/// Without config files, the metadata of the Cargo package and workspace is used instead.
+/// The settings shared with rustfmt and `.editorconfig` are used for everything that is not set,
+/// and the environment variables and the overrides on the command line are applied on top.
+pub struct Resolver {
+    /// The config file given on the command line, which is used instead of discovering them
+    config_file: Option<PathBuf>,
+    overrides: Vec<Override>,
+    config_files: FileCache<Table>,
+    manifests: FileCache<Table>,
+    rustfmt_files: FileCache<Table>,
+    editorconfigs: FileCache<EditorConfig>,
+}
+
+impl Resolver {
+    /// Validates the overrides, those of the environment variables are applied before `overrides`
+    pub fn new(config_file: Option<PathBuf>, overrides: Vec<Override>) -> anyhow::Result<Self> {
+        Self::with_env(config_file, |name| env::var(name).ok(), overrides)
+    }
+
+    /// Like `new`, but reads the environment variables with `var`
+    fn with_env(
+        config_file: Option<PathBuf>,
+        var: impl Fn(&str) -> Option<String>,
+        overrides: Vec<Override>,
+    ) -> anyhow::Result<Self> {
+        let overrides: Vec<_> = Override::from_env(var)
+            .into_iter()
+            .chain(overrides)
+            .collect();
+        for config_override in &overrides {
+            config_override.validate()?;
+        }
+
+        Ok(Self {
+            config_file,
+            overrides,
+            config_files: FileCache::default(),
+            manifests: FileCache::default(),
+            rustfmt_files: FileCache::default(),
+            editorconfigs: FileCache::default(),
+        })
+    }
+
+    pub fn config_for(&self, dir: &Path) -> anyhow::Result<Config> {
+        let mut table = Table::new();
+        let mut ignore_dir = None;
+        for layer in self.layers(dir)? {
+            if layer.table.contains_key("ignore") {
+                ignore_dir = layer.dir;
+            }
+            table.extend(layer.table);
+        }
+
+        let mut config: Config = Value::Table(table).try_into()?;
+        config.dir = ignore_dir;
+        Ok(config)
+    }
+
+    pub fn settings_for(&self, dir: &Path) -> anyhow::Result<FormatterSettings> {
+        Ok(self.config_for(dir)?.settings)
+    }
+
+    /// The effective config of `dir` as TOML, with a comment naming the source of every value
+    pub fn describe(&self, dir: &Path) -> anyhow::Result<String> {
+        self.config_for(dir)?;
+
+        let mut values: BTreeMap<_, _> = Config::defaults()
+            .into_iter()
+            .map(|(key, value)| (key, (value, "default".to_owned())))
+            .collect();
+        for layer in self.layers(dir)? {
+            for (key, value) in layer.table {
+                values.insert(key, (value, layer.source.clone()));
+            }
+        }
+
+        let lines: Vec<_> = values
+            .iter()
+            .map(|(key, (value, source))| (format!("{key} = {value}"), source))
+            .collect();
+        let width = lines.iter().map(|(line, _)| line.len()).max().unwrap_or(0);
+        Ok(lines
+            .into_iter()
+            .map(|(line, source)| format!("{line:width$}  # {source}\n"))
+            .collect())
+    }
+
+    /// The layers of settings of `dir`, from the lowest to the highest priority
+    fn layers(&self, dir: &Path) -> anyhow::Result<Vec<Layer>> {
+        let mut layers = Vec::new();
+        let config_files = match &self.config_file {
+            Some(config_file) => vec![config_file.clone()],
+            None => {
+                layers.extend(self.editorconfig(dir)?);
+                layers.extend(self.rustfmt_config(dir)?);
+                find_files(dir, CONFIG_FILE)
+            }
+        };
+
+        if config_files.is_empty() {
+            layers.extend(self.cargo_metadata(dir)?);
+        }
+        for config_file in config_files {
+            layers.push(Layer {
+                table: self.read_config_file(&config_file)?,
+                dir: Some(parent_dir(&config_file)?),
+                source: config_file.display().to_string(),
+            });
+        }
+
+        layers.extend(self.overrides.iter().map(|config_override| Layer {
+            source: config_override.source.clone(),
+            dir: None,
+            table: Table::from_iter([(config_override.key.clone(), config_override.value.clone())]),
+        }));
+        Ok(layers)
+    }
+
+    /// Reads and validates a config file, every file is only read once
+    fn read_config_file(&self, config_file: &Path) -> anyhow::Result<Table> {
+        self.config_files.get_or_read(config_file, |contents| {
+            if self.config_file.is_none() {
+                eprintln!("Discovered config at {}", config_file.display());
+            }
+
+            // deserialize the file on its own first, so errors point at the line in the file
+            toml::from_str::<Config>(contents)
+                .with_context(|| format!("invalid config file {}", config_file.display()))?;
+            let table = toml::from_str(contents)?;
+            validate(&table, &config_file.display().to_string())?;
+            Ok(table)
+        })
+    }
+
+    /// The settings in `[workspace.metadata.leptosfmt]` of the closest workspace and in
+    /// `[package.metadata.leptosfmt]` of the closest package, so packages inherit the settings of the workspace
+    fn cargo_metadata(&self, dir: &Path) -> anyhow::Result<Vec<Layer>> {
+        let mut manifests = Vec::new();
+        for path in find_files(dir, "Cargo.toml").into_iter().rev() {
+            manifests.push((self.read_manifest(&path)?, path));
+        }
+
+        let package = manifests
+            .iter()
+            .find(|(manifest, _)| manifest.contains_key("package"));
+        let workspace = manifests
+            .iter()
+            .find(|(manifest, _)| manifest.contains_key("workspace"));
+
+        let mut layers = Vec::new();
+        for (section, manifest) in [("workspace", workspace), ("package", package)] {
+            let Some((manifest, path)) = manifest else {
+                continue;
+            };
+            let Some(table) = metadata(manifest, section) else {
+                continue;
+            };
+
+            let source = format!("[{section}.metadata.leptosfmt] of {}", path.display());
+            validate(table, &source)?;
+
+            layers.push(Layer {
+                source,
+                dir: Some(parent_dir(path)?),
+                table: table.clone(),
+            });
+        }
+        Ok(layers)
+    }
+
+    /// Reads a Cargo manifest, every manifest is only read once
+    fn read_manifest(&self, path: &Path) -> anyhow::Result<Table> {
+        self.manifests.get_or_read(path, |contents| {
+            let manifest: Table =
+                toml::from_str(contents).with_context(|| format!("invalid {}", path.display()))?;
+
+            for section in ["workspace", "package"] {
+                if metadata(&manifest, section).is_some() {
+                    eprintln!(
+                        "Discovered config in [{section}.metadata.leptosfmt] of {}",
+                        path.display()
+                    );
+                }
+            }
+            Ok(manifest)
+        })
+    }
+
+    /// The settings of the closest rustfmt config file that leptosfmt shares with rustfmt
+    fn rustfmt_config(&self, dir: &Path) -> anyhow::Result<Option<Layer>> {
+        let Some(path) = dir
+            .ancestors()
+            .flat_map(|dir| RUSTFMT_CONFIG_FILES.map(|name| dir.join(name)))
+            .find(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+
+        let table = self.rustfmt_files.get_or_read(&path, |contents| {
+            let rustfmt_config: Table =
+                toml::from_str(contents).with_context(|| format!("invalid {}", path.display()))?;
+            warn_unsupported_rustfmt_settings(&rustfmt_config, &path);
+
+            // rustfmt accepts values that leptosfmt doesn't, which are skipped like those of `.editorconfig`
+            let source = path.display().to_string();
+            Ok(rustfmt_config
+                .into_iter()
+                .filter(|(key, _)| RUSTFMT_SETTINGS.contains(&key.as_str()))
+                .filter(|(key, value)| {
+                    let setting = Table::from_iter([(key.clone(), value.clone())]);
+                    match validate(&setting, &source) {
+                        Ok(()) => true,
+                        Err(err) => {
+                            eprintln!("note: ignoring {err:#}");
+                            false
+                        }
+                    }
+                })
+                .collect())
+        })?;
+
+        Ok(Some(Layer {
+            source: path.display().to_string(),
+            dir: None,
+            table,
+        }))
+    }
+
+    /// The settings of the `.editorconfig` files that apply to the Rust files in `dir`:
+    /// `indent_size` (or `tab_width` if it is `tab`) and `max_line_length`.
+    /// Every setting is a layer of its own, as they can come from different files.
+    fn editorconfig(&self, dir: &Path) -> anyhow::Result<Vec<Layer>> {
+        let mut editorconfigs = Vec::new();
+        for path in dir.ancestors().map(|dir| dir.join(".editorconfig")) {
+            if !path.is_file() {
+                continue;
+            }
+
+            let editorconfig = self
+                .editorconfigs
+                .get_or_read(&path, |contents| Ok(EditorConfig::parse(contents)))?;
+            let root = editorconfig.root;
+            editorconfigs.push((path, editorconfig));
+            if root {
+                break;
+            }
+        }
+
+        // the closer files override the properties of the outer ones
+        let mut properties = HashMap::new();
+        for (path, editorconfig) in editorconfigs.iter().rev() {
+            let editorconfig_dir = path.parent().unwrap_or(Path::new(""));
+            // settings are resolved per directory, so the sections are matched against any Rust file in it
+            let rust_file = dir
+                .strip_prefix(editorconfig_dir)
+                .unwrap_or(dir)
+                .join("*.rs");
+            properties.extend(
+                editorconfig
+                    .properties(&rust_file)
+                    .map(|(name, value)| (name.to_owned(), (value.to_owned(), path))),
+            );
+        }
+
+        let number = |name: &str| {
+            let (value, path) = properties.get(name)?;
+            Some((value.parse::<i64>().ok()?, path))
+        };
+        let indent_size = match properties.get("indent_size") {
+            Some((value, _)) if value == "tab" => number("tab_width"),
+            _ => number("indent_size"),
+        };
+
+        let mut layers = Vec::new();
+        for (key, setting) in [
+            ("tab_spaces", indent_size),
+            ("max_width", number("max_line_length")),
+        ] {
+            let Some((value, path)) = setting else {
+                continue;
+            };
+
+            let layer = Layer {
+                source: path.display().to_string(),
+                dir: None,
+                table: Table::from_iter([(key.to_owned(), Value::Integer(value))]),
+            };
+            // `.editorconfig` is shared with other tools, so values leptosfmt doesn't accept are ignored
+            if validate(&layer.table, &layer.source).is_ok() {
+                layers.push(layer);
+            }
+        }
+        Ok(layers)
+    }
+}
+
+/// Checks that every key of `table` is a setting and that its value is valid,
+/// `source` is where the table was read from
+fn validate(table: &Table, source: &str) -> anyhow::Result<()> {
+    let keys = Config::keys();
+    for (key, value) in table {
+        if !keys.contains(key) {
+            match keys.iter().find(|k| strsim::jaro_winkler(k, key) > 0.8) {
+                Some(similar) => {
+                    bail!("unknown setting `{key}` in {source}, did you mean `{similar}`?")
+                }
+                None => bail!(
+                    "unknown setting `{key}` in {source}, expected one of: {}",
+                    keys.join(", ")
+                ),
+            }
+        }
+
+        let invalid = || format!("invalid value for `{key}` in {source}");
+        Value::Table(Table::from_iter([(key.clone(), value.clone())]))
+            .try_into::<Config>()
+            .with_context(invalid)?;
+
+        let range = RANGES.iter().find(|(name, _)| name == key);
+        if let (Some((_, range)), Some(number)) = (range, value.as_integer()) {
+            if !range.contains(&number) {
+                return Err(anyhow!(
+                    "expected a number from {} to {}, found {number}",
+                    range.start(),
+                    range.end()
+                ))
+                .with_context(invalid);
+            }
+        }
+
+        if key == "ignore" {
+            let patterns: Vec<String> = value.clone().try_into()?;
+            input::matcher(Path::new(""), &patterns).with_context(invalid)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Notes the settings of rustfmt that apply to view macros as well, but are not supported by leptosfmt
+fn warn_unsupported_rustfmt_settings(rustfmt_config: &Table, path: &Path) {
+    if rustfmt_config.get("hard_tabs") == Some(&Value::Boolean(true)) {
+        eprintln!(
+            "note: `hard_tabs` of {} is not supported, view! macros are indented with spaces",
+            path.display()
+        );
+    }
+
+    // `Native` only means `\r\n` on Windows
+    let newline_style = rustfmt_config.get("newline_style").and_then(Value::as_str);
+    if let Some(newline_style) =
+        newline_style.filter(|style| *style == "Windows" || (cfg!(windows) && *style == "Native"))
+    {
+        eprintln!(
+            "note: `newline_style = \"{newline_style}\"` of {} is not supported, view! macros are formatted with `\\n` line endings",
+            path.display()
+        );
+    }
+}
+
+/// Files that are only read once, by their path
+struct FileCache<T>(Mutex<HashMap<PathBuf, T>>);
+
+impl<T: Clone> FileCache<T> {
+    /// Reads and parses the file the first time, returning the cached value afterwards
+    fn get_or_read(
+        &self,
+        path: &Path,
+        parse: impl FnOnce(&str) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let mut files = self.0.lock().unwrap();
+        if let Some(value) = files.get(path) {
+            return Ok(value.clone());
+        }
+
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("could not read {}", path.display()))?;
+        let value = parse(&contents)?;
+        files.insert(path.to_owned(), value.clone());
+        Ok(value)
+    }
+}
+
+impl<T> Default for FileCache<T> {
+    fn default() -> Self {
+        Self(Mutex::default())
+    }
+}
+
+/// The `[<section>.metadata.leptosfmt]` table of a Cargo manifest
+fn metadata<'a>(manifest: &'a Table, section: &str) -> Option<&'a Table> {
+    manifest
+        .get(section)?
+        .get("metadata")?
+        .get("leptosfmt")?
+        .as_table()
+}
+
+/// A `key=value` override of a setting in the config files, the value is parsed as TOML
+#[derive(Clone, Debug)]
+pub struct Override {
+    pub key: String,
+    pub value: Value,
+    /// Where the override comes from, e.g. `--config` or an environment variable
+    pub source: String,
+}
+
+impl Override {
+    pub fn new(key: &str, value: impl Into<Value>, source: &str) -> Self {
+        Self {
+            key: key.to_owned(),
+            value: value.into(),
+            source: source.to_owned(),
+        }
+    }
+
+    /// The overrides of the `LEPTOSFMT_*` environment variables, e.g. `LEPTOSFMT_MAX_WIDTH=120`
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Vec<Self> {
+        Config::keys()
+            .into_iter()
+            .filter_map(|key| {
+                let name = format!("{ENV_PREFIX}{}", key.to_uppercase());
+                let value = var(&name)?;
+                Some(Self::new(&key, parse_value(&value), &name))
+            })
+            .collect()
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        validate(
+            &Table::from_iter([(self.key.clone(), self.value.clone())]),
+            &self.source,
+        )
+    }
+}
+
+impl FromStr for Override {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((key, value)) = s.split_once('=') else {
+            return Err(format!("expected KEY=VALUE, found `{s}`"));
+        };
+
+        Ok(Self::new(key.trim(), parse_value(value), "--config"))
+    }
+}
+
+/// Parses a value as TOML, falling back to a string so strings don't need to be quoted,
+/// e.g. `attr_value_brace_style=Always`
+fn parse_value(value: &str) -> Value {
+    match toml::from_str::<Table>(&format!("value = {value}")) {
+        Ok(mut table) if table.len() == 1 => table.remove("value").unwrap(),
+        _ => Value::String(value.to_owned()),
+    }
+}
+
+/// Finds the files named `name` in `dir` and its parents, the outermost first
+fn find_files(dir: &Path, name: &str) -> Vec<PathBuf> {
+    let mut files: Vec<_> = dir
+        .ancestors()
+        .map(|dir| dir.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+    files.reverse();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `files` to a temporary directory and returns its canonical path,
+    /// as the resolver is called with canonical directories
+    fn files(files: &[(&str, &str)]) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, contents) in files {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        let path = fs::canonicalize(dir.path()).unwrap();
+        (dir, path)
+    }
+
+    fn resolver(env: &[(&str, &str)], overrides: &[&str]) -> Resolver {
+        let env: HashMap<_, _> = env
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let overrides = overrides.iter().map(|o| o.parse().unwrap()).collect();
+        Resolver::with_env(None, |name| env.get(name).cloned(), overrides).unwrap()
+    }
+
+    fn widths(settings: FormatterSettings) -> (usize, usize) {
+        (settings.max_width, settings.tab_spaces)
+    }
+
+    #[test]
+    fn innermost_config_file_wins() {
+        let (_temp, dir) = files(&[
+            ("leptosfmt.toml", "max_width = 90\ntab_spaces = 2"),
+            ("crates/legacy/leptosfmt.toml", "max_width = 120"),
+        ]);
+        let resolver = resolver(&[], &[]);
+
+        let settings_for = |path: &str| widths(resolver.settings_for(&dir.join(path)).unwrap());
+        assert_eq!(settings_for(""), (90, 2));
+        assert_eq!(settings_for("crates"), (90, 2));
+        assert_eq!(settings_for("crates/legacy"), (120, 2));
+        assert_eq!(settings_for("crates/legacy/src"), (120, 2));
+    }
+
+    #[test]
+    fn env_beats_config_files() {
+        let (_temp, dir) = files(&[("leptosfmt.toml", "max_width = 90\ntab_spaces = 2")]);
+        let resolver = resolver(&[("LEPTOSFMT_TAB_SPACES", "3")], &[]);
+        assert_eq!(widths(resolver.settings_for(&dir).unwrap()), (90, 3));
+    }
+
+    #[test]
+    fn config_override_beats_env() {
+        let (_temp, dir) = files(&[("leptosfmt.toml", "max_width = 90\ntab_spaces = 2")]);
+        let resolver = resolver(&[("LEPTOSFMT_TAB_SPACES", "3")], &["tab_spaces=8"]);
+        assert_eq!(widths(resolver.settings_for(&dir).unwrap()), (90, 8));
+    }
+
+    #[test]
+    fn cargo_metadata_without_config_file() {
+        let workspace = "[workspace]\nmembers = [\"app\"]\n[workspace.metadata.leptosfmt]\nmax_width = 90\ntab_spaces = 2";
+        let package = "[package]\nname = \"app\"\n[package.metadata.leptosfmt]\ntab_spaces = 3";
+        let (_temp, dir) = files(&[("Cargo.toml", workspace), ("app/Cargo.toml", package)]);
+        let settings = resolver(&[], &[])
+            .settings_for(&dir.join("app/src"))
+            .unwrap();
+        assert_eq!(widths(settings), (90, 3));
+
+        // the metadata is ignored as soon as there is a config file
+        fs::write(dir.join("leptosfmt.toml"), "tab_spaces = 8").unwrap();
+        let settings = resolver(&[], &[])
+            .settings_for(&dir.join("app/src"))
+            .unwrap();
+        assert_eq!(
+            widths(settings),
+            (FormatterSettings::default().max_width, 8)
+        );
+    }
+
+    #[test]
+    fn rustfmt_config_beats_editorconfig() {
+        let editorconfig = "root = true\n[*.rs]\nindent_size = 2\nmax_line_length = 90";
+        let (_temp, dir) = files(&[
+            (".editorconfig", editorconfig),
+            ("rustfmt.toml", "max_width = 120"),
+        ]);
+        let settings = resolver(&[], &[]).settings_for(&dir).unwrap();
+        assert_eq!(widths(settings), (120, 2));
+    }
+
+    #[test]
+    fn invalid_rustfmt_values_are_skipped() {
+        let editorconfig = "root = true\n[*.rs]\nindent_size = 2\nmax_line_length = 90";
+        let (_temp, dir) = files(&[
+            (".editorconfig", editorconfig),
+            (
+                "rustfmt.toml",
+                "max_width = 120\ntab_spaces = 20\nhard_tabs = true",
+            ),
+        ]);
+        let settings = resolver(&[], &[]).settings_for(&dir).unwrap();
+        assert_eq!(widths(settings), (120, 2));
+    }
+
+    fn validate_toml(toml: &str) -> Result<(), String> {
+        validate(&toml::from_str(toml).unwrap(), "test").map_err(|err| format!("{err:#}"))
+    }
+
+    #[test]
+    fn validate_settings() {
+        assert_eq!(
+            validate_toml("max_width = 80\ntab_spaces = 2\nignore = [\"gen/\"]"),
+            Ok(())
+        );
+        assert_eq!(
+            validate_toml("max_widht = 80"),
+            Err("unknown setting `max_widht` in test, did you mean `max_width`?".to_owned())
+        );
+        assert_eq!(
+            validate_toml("indent = 2"),
+            Err("unknown setting `indent` in test, expected one of: attr_value_brace_style, ignore, max_width, tab_spaces".to_owned())
+        );
+    }
+
+    #[test]
+    fn validate_values() {
+        assert_eq!(
+            validate_toml("tab_spaces = 0"),
+            Err(
+                "invalid value for `tab_spaces` in test: expected a number from 1 to 16, found 0"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            validate_toml("max_width = 1001"),
+            Err("invalid value for `max_width` in test: expected a number from 10 to 1000, found 1001".to_owned())
+        );
+        assert!(validate_toml("max_width = \"wide\"")
+            .unwrap_err()
+            .starts_with("invalid value for `max_width` in test: "));
+        assert!(validate_toml("attr_value_brace_style = \"Never\"")
+            .unwrap_err()
+            .starts_with("invalid value for `attr_value_brace_style` in test: "));
+        assert!(validate_toml("ignore = [\"a{\"]")
+            .unwrap_err()
+            .starts_with("invalid value for `ignore` in test: invalid exclude pattern: a{"));
+    }
+
+    #[test]
+    fn describe_sources() {
+        let (_temp, dir) = files(&[("leptosfmt.toml", "max_width = 90\ntab_spaces = 8")]);
+        let resolver = resolver(
+            &[("LEPTOSFMT_TAB_SPACES", "2")],
+            &["attr_value_brace_style=Always"],
+        );
+
+        let config_file = dir.join("leptosfmt.toml").display().to_string();
+        assert_eq!(
+            resolver.describe(&dir).unwrap(),
+            format!(
+                "attr_value_brace_style = \"Always\"  # --config\n\
+                 ignore = []                        # default\n\
+                 max_width = 90                     # {config_file}\n\
+                 tab_spaces = 2                     # LEPTOSFMT_TAB_SPACES\n"
+            )
+        );
+    }
+
+    #[test]
+    fn parse_values() {
+        assert_eq!(parse_value("120"), Value::Integer(120));
+        assert_eq!(parse_value("true"), Value::Boolean(true));
+        assert_eq!(parse_value("Always"), Value::String("Always".to_owned()));
+        assert_eq!(
+            parse_value("\"Always\""),
+            Value::String("Always".to_owned())
+        );
+        assert_eq!(
+            parse_value("[\"a\", \"b\"]"),
+            Value::Array(vec!["a".into(), "b".into()])
+        );
+        // anything that isn't a single TOML value is a string
+        assert_eq!(parse_value("a = b"), Value::String("a = b".to_owned()));
+    }
+
+    #[test]
+    fn parse_override() {
+        let config_override: Override = " tab_spaces = 2 ".parse().unwrap();
+        assert_eq!(config_override.key, "tab_spaces");
+        assert_eq!(config_override.value, Value::Integer(2));
+        assert_eq!(config_override.source, "--config");
+
+        let config_override: Override = "ignore=[\"gen/\"]".parse().unwrap();
+        assert_eq!(config_override.value, Value::Array(vec!["gen/".into()]));
+
+        assert_eq!(
+            "max_width".parse::<Override>().unwrap_err(),
+            "expected KEY=VALUE, found `max_width`"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::{error::Error, fmt, fmt::Write, path::Path};
+
+use leptosfmt_formatter::FormatError;
+
+use crate::report::Position;
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const BLUE: &str = "\x1b[34m";
+const RESET: &str = "\x1b[0m";
+
+/// An error in a source file that is rendered like a compiler error, with the offending span
+/// underlined in the source line it starts on
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// What went wrong, e.g. "could not parse view! macro"
+    pub message: String,
+    /// What is wrong with the underlined span, e.g. the error of the parser
+    pub label: String,
+    pub start: Position,
+    pub end: Position,
+    line: String,
+}
+
+impl Diagnostic {
+    /// A diagnostic for the text between `start` and `end` in `source`
+    pub fn new(
+        message: impl Into<String>,
+        label: impl Into<String>,
+        source: &str,
+        start: Position,
+        end: Position,
+    ) -> Option<Self> {
+        let line = source.lines().nth(start.line.checked_sub(1)?)?;
+
+        Some(Self {
+            message: message.into(),
+            label: label.into(),
+            start,
+            end,
+            line: line.to_owned(),
+        })
+    }
+
+    /// Locates a parse error of the formatter in the source it was formatting
+    pub fn from_format_error(err: &FormatError, source: &str) -> Option<Self> {
+        let syn_err = err.syn_error()?;
+        let (start, end) = (syn_err.span().start(), syn_err.span().end());
+
+        Self::new(
+            err.to_string(),
+            syn_err.to_string(),
+            source,
+            Position {
+                line: start.line,
+                column: start.column + 1,
+            },
+            Position {
+                line: end.line,
+                column: end.column + 1,
+            },
+        )
+    }
+
+    pub fn render(&self, path: &Path, colored: bool) -> String {
+        let paint = |color: &str, text: &str| {
+            if colored {
+                format!("{color}{text}{RESET}")
+            } else {
+                text.to_owned()
+            }
+        };
+
+        let line_number = self.start.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let bar = paint(BLUE, "|");
+
+        // keep tabs in the indentation of the markers, so they line up with the source line
+        let before: String = self
+            .line
+            .chars()
+            .take(self.start.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let line_length = self.line.chars().count() + 1;
+        let end_column = if self.end.line == self.start.line {
+            self.end.column.min(line_length)
+        } else {
+            line_length
+        };
+        let markers = "^".repeat(end_column.saturating_sub(self.start.column).max(1));
+
+        let mut output = String::new();
+        writeln!(
+            output,
+            "{}{}",
+            paint(RED, "error"),
+            paint(BOLD, &format!(": {}", self.message))
+        )
+        .unwrap();
+        writeln!(
+            output,
+            "{gutter}{} {}:{}:{}",
+            paint(BLUE, "-->"),
+            path.display(),
+            self.start.line,
+            self.start.column
+        )
+        .unwrap();
+        writeln!(output, "{gutter} {bar}").unwrap();
+        writeln!(output, "{} {bar} {}", paint(BLUE, &line_number), self.line).unwrap();
+        writeln!(
+            output,
+            "{gutter} {bar} {before}{}",
+            paint(RED, &format!("{markers} {}", self.label))
+        )
+        .unwrap();
+        output
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.message, self.label)
+    }
+}
+
+impl Error for Diagnostic {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(source: &str, start: (usize, usize), end: (usize, usize)) -> Diagnostic {
+        let position = |(line, column)| Position { line, column };
+        Diagnostic::new("message", "label", source, position(start), position(end)).unwrap()
+    }
+
+    fn render(diagnostic: &Diagnostic) -> String {
+        diagnostic.render(Path::new("src/main.rs"), false)
+    }
+
+    #[test]
+    fn render_without_colors() {
+        let source = "fn main() {\n    let x = 1;\n}\n";
+        assert_eq!(
+            render(&diagnostic(source, (2, 9), (2, 10))),
+            "error: message\n \
+             --> src/main.rs:2:9\n  \
+             |\n\
+             2 |     let x = 1;\n  \
+             |         ^ label\n"
+        );
+    }
+
+    #[test]
+    fn render_with_colors() {
+        let rendered = diagnostic("let x = 1;", (1, 5), (1, 6)).render(Path::new("a.rs"), true);
+        assert!(rendered.starts_with("\x1b[31merror\x1b[0m\x1b[1m: message\x1b[0m\n"));
+        assert!(rendered.contains("\x1b[31m^ label\x1b[0m"));
+    }
+
+    #[test]
+    fn markers_keep_tabs() {
+        let source = "fn main() {\n\tlet x = 1;\n}\n";
+        let rendered = render(&diagnostic(source, (2, 6), (2, 7)));
+        assert!(rendered.ends_with("2 | \tlet x = 1;\n  | \t    ^ label\n"));
+    }
+
+    #[test]
+    fn multi_line_span_is_underlined_to_the_end_of_the_line() {
+        let source = "fn main() {\n    view! { cx,\n    };\n}\n";
+        let rendered = render(&diagnostic(source, (2, 5), (3, 6)));
+        assert!(rendered.ends_with("2 |     view! { cx,\n  |     ^^^^^^^^^^^ label\n"));
+    }
+
+    #[test]
+    fn gutter_fits_line_number() {
+        let source = "\n".repeat(9) + "let x = 1;";
+        let rendered = render(&diagnostic(&source, (10, 5), (10, 6)));
+        assert!(
+            rendered.contains("  --> src/main.rs:10:5\n   |\n10 | let x = 1;\n   |     ^ label\n")
+        );
+    }
+
+    #[test]
+    fn position_outside_of_source() {
+        let position = Position { line: 3, column: 1 };
+        assert!(Diagnostic::new("message", "label", "fn main() {}", position, position).is_none());
+    }
+}
@@ -0,0 +1,107 @@
+use std::{fmt::Write, path::Path};
+
+use similar::{ChangeTag, TextDiff};
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders a unified diff between the original and the formatted source of `path`
+pub fn unified_diff(path: &Path, original: &str, formatted: &str, colored: bool) -> String {
+    let paint = |color: &str, text: &str| {
+        if colored {
+            format!("{color}{text}{RESET}")
+        } else {
+            text.to_owned()
+        }
+    };
+
+    // absolute paths can't be prefixed with `a/` and `b/`, so they are used as they are
+    let name = path.display();
+    let (old, new) = if path.is_absolute() {
+        (name.to_string(), name.to_string())
+    } else {
+        (format!("a/{name}"), format!("b/{name}"))
+    };
+    let mut output = String::new();
+    writeln!(output, "{}", paint(BOLD, &format!("--- {old}"))).unwrap();
+    writeln!(output, "{}", paint(BOLD, &format!("+++ {new}"))).unwrap();
+
+    let diff = TextDiff::from_lines(original, formatted);
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        writeln!(output, "{}", paint(CYAN, &hunk.header().to_string())).unwrap();
+
+        for change in hunk.iter_changes() {
+            let line = change.value().trim_end_matches(['\n', '\r']);
+            let line = match change.tag() {
+                ChangeTag::Delete => paint(RED, &format!("-{line}")),
+                ChangeTag::Insert => paint(GREEN, &format!("+{line}")),
+                ChangeTag::Equal => format!(" {line}"),
+            };
+
+            writeln!(output, "{line}").unwrap();
+            if change.missing_newline() {
+                writeln!(output, "\\ No newline at end of file").unwrap();
+            }
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = "fn main() {\n    view! {   cx, <div/> };\n}\n";
+    const FORMATTED: &str = "fn main() {\n    view! { cx, <div></div> };\n}\n";
+
+    #[test]
+    fn hunks() {
+        assert_eq!(
+            unified_diff(Path::new("src/main.rs"), ORIGINAL, FORMATTED, false),
+            "--- a/src/main.rs\n\
+             +++ b/src/main.rs\n\
+             @@ -1,3 +1,3 @@\n \
+             fn main() {\n\
+             -    view! {   cx, <div/> };\n\
+             +    view! { cx, <div></div> };\n \
+             }\n"
+        );
+    }
+
+    #[test]
+    fn colored_hunks() {
+        let diff = unified_diff(Path::new("src/main.rs"), ORIGINAL, FORMATTED, true);
+        assert!(
+            diff.starts_with("\x1b[1m--- a/src/main.rs\x1b[0m\n\x1b[1m+++ b/src/main.rs\x1b[0m\n")
+        );
+        assert!(diff.contains("\x1b[36m@@ -1,3 +1,3 @@\x1b[0m\n"));
+        assert!(diff.contains("\x1b[31m-    view! {   cx, <div/> };\x1b[0m\n"));
+        assert!(diff.contains("\x1b[32m+    view! { cx, <div></div> };\x1b[0m\n"));
+        assert!(diff.contains("\n }\n"));
+    }
+
+    #[test]
+    fn absolute_path() {
+        let path = std::env::current_dir().unwrap().join("main.rs");
+        let diff = unified_diff(&path, ORIGINAL, FORMATTED, false);
+        let name = path.display();
+        assert!(diff.starts_with(&format!("--- {name}\n+++ {name}\n")));
+    }
+
+    #[test]
+    fn no_newline_at_end_of_file() {
+        let diff = unified_diff(
+            Path::new("a.rs"),
+            "let a = 1;\nlet b = 2;",
+            "let a = 1;\nlet b = 3;",
+            false,
+        );
+        assert!(diff.ends_with(
+            "-let b = 2;\n\\ No newline at end of file\n+let b = 3;\n\\ No newline at end of file\n"
+        ));
+    }
+}
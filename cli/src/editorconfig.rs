@@ -0,0 +1,175 @@
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+/// A parsed `.editorconfig` file, see <https://editorconfig.org>
+#[derive(Clone, Debug, Default)]
+pub struct EditorConfig {
+    /// Whether the search for `.editorconfig` files in the parent directories stops at this file
+    pub root: bool,
+    sections: Vec<Section>,
+}
+
+#[derive(Clone, Debug)]
+struct Section {
+    /// Matches the paths relative to the directory of the file, `None` if the pattern is invalid
+    matcher: Option<GlobMatcher>,
+    properties: Vec<(String, String)>,
+}
+
+impl EditorConfig {
+    /// Parses the INI-like format of `.editorconfig`, skipping lines that can't be parsed like editors do.
+    /// Names and values are lowercased, as they are case-insensitive.
+    pub fn parse(contents: &str) -> Self {
+        let mut editorconfig = Self::default();
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(pattern) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                editorconfig.sections.push(Section {
+                    matcher: matcher(pattern),
+                    properties: Vec::new(),
+                });
+                continue;
+            }
+
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (name, value) = (name.trim().to_lowercase(), value.trim().to_lowercase());
+            match editorconfig.sections.last_mut() {
+                Some(section) => section.properties.push((name, value)),
+                None if name == "root" => editorconfig.root = value == "true",
+                None => {}
+            }
+        }
+
+        editorconfig
+    }
+
+    /// The properties that apply to `path`, relative to the directory of the file.
+    /// Later properties override earlier ones with the same name.
+    pub fn properties<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.sections
+            .iter()
+            .filter(
+                move |section| matches!(&section.matcher, Some(matcher) if matcher.is_match(path)),
+            )
+            .flat_map(|section| &section.properties)
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+}
+
+/// Patterns without a `/` match files in any subdirectory, others are relative to the directory of the file.
+/// `**` only matches across directories as a whole path component, e.g. `src/**/*.rs`.
+fn matcher(pattern: &str) -> Option<GlobMatcher> {
+    let pattern = match pattern.strip_prefix('/') {
+        Some(pattern) => pattern.to_owned(),
+        None if pattern.contains('/') => pattern.to_owned(),
+        None => format!("**/{pattern}"),
+    };
+
+    let glob = GlobBuilder::new(&pattern)
+        .literal_separator(true)
+        .build()
+        .ok()?;
+    Some(glob.compile_matcher())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn properties(editorconfig: &EditorConfig, path: &str) -> Vec<(String, String)> {
+        editorconfig
+            .properties(Path::new(path))
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    fn property(name: &str, value: &str) -> (String, String) {
+        (name.to_owned(), value.to_owned())
+    }
+
+    #[test]
+    fn parse() {
+        let editorconfig = EditorConfig::parse(
+            "# comment\n\
+             ; comment\n\
+             ROOT = TRUE\n\
+             indent_size = 8\n\
+             \n\
+             [*]\n\
+             Indent_Style = Space\n\
+             not a property\n\
+             [*.rs]\n\
+             indent_size = 2\n",
+        );
+        assert!(editorconfig.root);
+        // properties before the first section don't apply to any file
+        assert_eq!(
+            properties(&editorconfig, "main.rs"),
+            vec![
+                property("indent_style", "space"),
+                property("indent_size", "2")
+            ]
+        );
+        assert_eq!(
+            properties(&editorconfig, "README.md"),
+            vec![property("indent_style", "space")]
+        );
+        assert!(!EditorConfig::parse("[*]\nroot = true").root);
+    }
+
+    #[test]
+    fn patterns_without_slash_match_in_subdirectories() {
+        let editorconfig = EditorConfig::parse("[*.rs]\nindent_size = 2");
+        for path in ["main.rs", "src/main.rs", "crates/app/src/main.rs"] {
+            assert_eq!(
+                properties(&editorconfig, path),
+                vec![property("indent_size", "2")],
+                "{path}"
+            );
+        }
+        assert!(properties(&editorconfig, "main.rs.bak").is_empty());
+    }
+
+    #[test]
+    fn patterns_with_slash_are_relative() {
+        let editorconfig = EditorConfig::parse(
+            "[/main.rs]\nmax_line_length = 80\n[src/*.rs]\nindent_size = 2\n[src/**/*.rs]\ntab_width = 3",
+        );
+        assert_eq!(
+            properties(&editorconfig, "main.rs"),
+            vec![property("max_line_length", "80")]
+        );
+        assert!(properties(&editorconfig, "app/main.rs").is_empty());
+        assert_eq!(
+            properties(&editorconfig, "src/lib.rs"),
+            vec![property("indent_size", "2"), property("tab_width", "3")]
+        );
+        // `*` doesn't match `/`, `**` does
+        assert_eq!(
+            properties(&editorconfig, "src/app/mod.rs"),
+            vec![property("tab_width", "3")]
+        );
+        assert!(properties(&editorconfig, "crates/src/lib.rs").is_empty());
+    }
+
+    #[test]
+    fn braces_and_invalid_patterns() {
+        let editorconfig =
+            EditorConfig::parse("[*.{rs,toml}]\nindent_size = 2\n[*.{rs]\nindent_size = 3");
+        assert_eq!(
+            properties(&editorconfig, "src/main.rs"),
+            vec![property("indent_size", "2")]
+        );
+        assert_eq!(
+            properties(&editorconfig, "Cargo.toml"),
+            vec![property("indent_size", "2")]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context};
+
+/// Rust files that were modified, added or are untracked compared to `rev`,
+/// including staged and unstaged changes in the working tree.
+///
+/// The files of the whole repository are returned, not only those in the current directory.
+/// Files in the current directory are relative to it, the others are absolute.
+pub fn changed_files(rev: &str) -> anyhow::Result<Vec<PathBuf>> {
+    changed_files_in(&env::current_dir()?, rev)
+}
+
+/// The changed files of the repository containing `dir`, relative to `dir` if they are in it
+fn changed_files_in(dir: &Path, rev: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let root = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim_end());
+
+    let changed = git(
+        dir,
+        &[
+            "diff",
+            "--name-only",
+            "-z",
+            "--no-renames",
+            "--diff-filter=d",
+            rev,
+            "--",
+        ],
+    )?;
+    let untracked = git(
+        dir,
+        &[
+            "ls-files",
+            "--others",
+            "--exclude-standard",
+            "--full-name",
+            "-z",
+            ":/",
+        ],
+    )?;
+
+    let mut files: Vec<_> = changed
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|path| Path::new(path).extension().is_some_and(|ext| ext == "rs"))
+        .map(|path| {
+            let path = root.join(path);
+            match path.strip_prefix(dir) {
+                Ok(relative) => relative.to_owned(),
+                Err(_) => path,
+            }
+        })
+        .collect();
+
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+fn git(dir: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .context("could not run git")?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn changed_rust_files() {
+        let dir = tempfile::tempdir().unwrap();
+        // git resolves the top level of the repository, so the paths are compared canonicalized
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let write = |path: &str, contents: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        let git = |args: &[&str]| {
+            let mut args = args.to_vec();
+            args.splice(
+                0..0,
+                ["-c", "user.name=test", "-c", "user.email=test@example.com"],
+            );
+            git(&root, &args).unwrap();
+        };
+
+        git(&["init", "--quiet"]);
+        for path in [
+            "modified.rs",
+            "staged.rs",
+            "deleted.rs",
+            "unchanged.rs",
+            "sub/unchanged.rs",
+            "README.md",
+        ] {
+            write(path, "");
+        }
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "initial"]);
+
+        write("modified.rs", "fn main() {}");
+        write("staged.rs", "fn main() {}");
+        git(&["add", "staged.rs"]);
+        fs::remove_file(root.join("deleted.rs")).unwrap();
+        write("untracked.rs", "");
+        write("sub/untracked.rs", "");
+        write("README.md", "changed");
+
+        assert_eq!(
+            changed_files_in(&root, "HEAD").unwrap(),
+            [
+                "modified.rs",
+                "staged.rs",
+                "sub/untracked.rs",
+                "untracked.rs"
+            ]
+            .map(PathBuf::from)
+        );
+
+        // from a subdirectory, the changed files of the whole repository are returned
+        let sub = root.join("sub");
+        assert_eq!(
+            changed_files_in(&sub, "HEAD").unwrap(),
+            [
+                root.join("modified.rs"),
+                root.join("staged.rs"),
+                root.join("untracked.rs"),
+                PathBuf::from("untracked.rs"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+};
+
+use anyhow::Context;
+use leptosfmt_formatter::FormatterSettings;
+
+/// Writes a config file with the default settings to `path`, without overwriting an existing file unless `force` is set
+pub fn init(path: &Path, force: bool) -> anyhow::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+
+    let mut file = match options.open(path) {
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+            anyhow::bail!(
+                "{} already exists, use --force to overwrite it",
+                path.display()
+            )
+        }
+        result => result.with_context(|| format!("could not create {}", path.display()))?,
+    };
+
+    file.write_all(default_config().as_bytes())?;
+    Ok(())
+}
+
+/// The default settings, with a comment explaining every option. The settings are commented out,
+/// so they don't override the settings of outer config files, `rustfmt.toml` or `.editorconfig`.
+fn default_config() -> String {
+    let settings = FormatterSettings::default();
+    format!(
+        r#"# Configuration of leptosfmt, the options are described in docs/configuration.md of {repository}
+# Uncomment a setting to override the value of the outer leptosfmt.toml files, rustfmt.toml or .editorconfig
+
+# Maximum width of each line
+# max_width = {max_width}
+
+# Number of spaces per indentation level
+# tab_spaces = {tab_spaces}
+
+# Whether to add braces around single expression attribute values:
+# - "Always": always add braces, e.g. `width={{100}}` and `class={{"banner"}}`
+# - "AlwaysUnlessLit": add braces unless the value is a literal, e.g. `width=100` and `disabled={{is_disabled}}`
+# - "WhenRequired": only keep braces that are required, e.g. `width=100` and `disabled=is_disabled`
+# - "Preserve": keep the braces as they are
+# attr_value_brace_style = "{attr_value_brace_style:?}"
+
+# Gitignore-style patterns of files that should not be formatted, relative to this file
+# ignore = []
+"#,
+        repository = env!("CARGO_PKG_REPOSITORY"),
+        max_width = settings.max_width,
+        tab_spaces = settings.tab_spaces,
+        attr_value_brace_style = settings.attr_value_brace_style,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn create_new_or_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("leptosfmt.toml");
+
+        init(&path, false).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), default_config());
+
+        fs::write(&path, "max_width = 80").unwrap();
+        let err = init(&path, false).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("already exists, use --force to overwrite it"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "max_width = 80");
+
+        init(&path, true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), default_config());
+    }
+
+    #[test]
+    fn default_config_parses() {
+        // nothing is set until a setting is uncommented
+        let config: Config = toml::from_str(&default_config()).unwrap();
+        assert_eq!(config.settings, FormatterSettings::default());
+
+        let uncommented: String = default_config()
+            .lines()
+            .map(|line| match line.strip_prefix("# ") {
+                Some(setting) if setting.contains(" = ") => setting,
+                _ => line,
+            })
+            .map(|line| format!("{line}\n"))
+            .collect();
+        let table: toml::Table = toml::from_str(&uncommented).unwrap();
+        let mut keys: Vec<_> = table.keys().cloned().collect();
+        keys.sort();
+        let mut expected = Config::keys();
+        expected.sort();
+        assert_eq!(keys, expected);
+
+        let config: Config = toml::from_str(&uncommented).unwrap();
+        assert_eq!(config.settings, FormatterSettings::default());
+        assert!(config.ignore.is_empty());
+    }
+}
@@ -0,0 +1,454 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    io::{self, Read},
+    ops::RangeInclusive,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Context;
+use glob::glob;
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    WalkBuilder,
+};
+
+use crate::config::Resolver;
+
+/// Name of the ignore file that is respected while walking directories
+const IGNORE_FILE: &str = ".leptosfmtignore";
+
+/// Gitignore-style patterns of files that should not be formatted: those of `--exclude`,
+/// and the `ignore` setting, which is resolved for the directory of every path like the other settings
+#[derive(Clone)]
+pub struct Excludes {
+    current_dir: PathBuf,
+    excludes: Option<Gitignore>,
+    resolver: Arc<Resolver>,
+    /// The `ignore` setting of every directory, `None` if nothing is ignored in it
+    ignores: Arc<Mutex<HashMap<PathBuf, Option<Arc<Ignore>>>>>,
+}
+
+/// The matcher of the `ignore` setting of a directory
+struct Ignore {
+    /// The canonical path of the directory, as the paths of the config files are canonical
+    dir: PathBuf,
+    matcher: Gitignore,
+}
+
+impl Excludes {
+    /// `excludes` are relative to the current directory
+    pub fn new(excludes: &[String], resolver: Arc<Resolver>) -> anyhow::Result<Self> {
+        let current_dir = env::current_dir()?;
+        let excludes = match excludes {
+            [] => None,
+            excludes => Some(matcher(&current_dir, excludes)?),
+        };
+
+        Ok(Self {
+            current_dir,
+            excludes,
+            resolver,
+            ignores: Arc::default(),
+        })
+    }
+
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        let path = self.current_dir.join(path);
+        let is_match = |matcher: &Gitignore, path: &Path| {
+            path.starts_with(matcher.path())
+                && matcher
+                    .matched_path_or_any_parents(path, is_dir)
+                    .is_ignore()
+        };
+
+        if self
+            .excludes
+            .as_ref()
+            .is_some_and(|excludes| is_match(excludes, &path))
+        {
+            return true;
+        }
+
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return false;
+        };
+        self.ignore(dir)
+            .is_some_and(|ignore| is_match(&ignore.matcher, &ignore.dir.join(name)))
+    }
+
+    /// The `ignore` setting of `dir`. Nothing is ignored when the config can't be resolved,
+    /// so the error is reported when formatting the files.
+    fn ignore(&self, dir: &Path) -> Option<Arc<Ignore>> {
+        let mut ignores = self.ignores.lock().unwrap();
+        if let Some(ignore) = ignores.get(dir) {
+            return ignore.clone();
+        }
+
+        let ignore = fs::canonicalize(dir).ok().and_then(|dir| {
+            let config = self.resolver.config_for(&dir).ok()?;
+            if config.ignore.is_empty() {
+                return None;
+            }
+            let root = config.dir.unwrap_or_else(|| self.current_dir.clone());
+            let matcher = matcher(&root, &config.ignore).ok()?;
+            Some(Arc::new(Ignore { dir, matcher }))
+        });
+        ignores.insert(dir.to_owned(), ignore.clone());
+        ignore
+    }
+}
+
+/// Matches the gitignore-style `patterns` relative to `root`
+pub fn matcher(root: &Path, patterns: &[String]) -> anyhow::Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .with_context(|| format!("invalid exclude pattern: {pattern}"))?;
+    }
+    Ok(builder.build()?)
+}
+
+/// A path that could not be collected, e.g. a directory that can't be read
+#[derive(Debug)]
+pub struct InputError {
+    pub path: PathBuf,
+    pub error: anyhow::Error,
+}
+
+impl InputError {
+    /// Takes the path out of the errors of the walk, so it isn't repeated in the message
+    fn from_walk(error: ignore::Error, dir: &Path) -> Self {
+        match error {
+            ignore::Error::WithPath { path, err } => Self {
+                error: Self::from_walk(*err, &path).error,
+                path,
+            },
+            ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+                Self::from_walk(*err, dir)
+            }
+            error => Self {
+                path: dir.to_owned(),
+                error: error.into(),
+            },
+        }
+    }
+}
+
+/// Collects the rust files matching a file, directory or glob.
+///
+/// Directories are walked recursively, skipping hidden files and files ignored by
+/// `.gitignore` or `.leptosfmtignore`.
+pub fn collect_files(input: &str, excludes: &Excludes) -> Vec<Result<PathBuf, InputError>> {
+    let is_dir = fs::metadata(input)
+        .map(|meta| meta.is_dir())
+        .unwrap_or(false);
+
+    if is_dir {
+        return walk_dir(Path::new(input), excludes);
+    }
+
+    let paths = match glob(input) {
+        Ok(paths) => paths,
+        Err(err) => {
+            return vec![Err(InputError {
+                path: PathBuf::from(input),
+                error: anyhow::anyhow!("invalid glob pattern: {err}"),
+            })]
+        }
+    };
+
+    paths
+        .filter(|result| match result {
+            Ok(path) => !excludes.is_excluded(path, false),
+            Err(_) => true,
+        })
+        .map(|result| {
+            result.map_err(|err| InputError {
+                path: err.path().to_owned(),
+                error: err.into_error().into(),
+            })
+        })
+        .collect()
+}
+
+fn walk_dir(dir: &Path, excludes: &Excludes) -> Vec<Result<PathBuf, InputError>> {
+    let excludes = excludes.clone();
+    WalkBuilder::new(dir)
+        .require_git(false)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        // walk in a stable order, so the files are reported in the same order on every run
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            !excludes.is_excluded(entry.path(), is_dir)
+        })
+        .build()
+        .filter_map(|result| match result {
+            Ok(entry) => {
+                let is_rust_file = entry.file_type().is_some_and(|t| t.is_file())
+                    && entry.path().extension().is_some_and(|ext| ext == "rs");
+                is_rust_file.then(|| Ok(entry.into_path()))
+            }
+            Err(err) => Some(Err(InputError::from_walk(err, dir))),
+        })
+        .collect()
+}
+
+/// Reads a list of paths separated by newlines or NUL characters from a file, or from stdin when `-`
+pub fn read_file_list(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut list = String::new();
+    if path == Path::new("-") {
+        io::stdin().read_to_string(&mut list)?;
+    } else {
+        list = fs::read_to_string(path)?;
+    }
+
+    Ok(parse_file_list(&list))
+}
+
+/// Splits a list of paths at NUL characters if it contains any, otherwise at (CR)LF line endings
+fn parse_file_list(list: &str) -> Vec<PathBuf> {
+    let separator = if list.contains('\0') { '\0' } else { '\n' };
+    list.split(separator)
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Removes paths that were matched by more than one input, keeping the first occurrence.
+/// Paths are compared by their canonical path, e.g. `a.rs` and `$PWD/a.rs` are the same file,
+/// or without `.` components when they don't exist.
+pub fn dedup<E>(paths: Vec<Result<PathBuf, E>>) -> Vec<Result<PathBuf, E>> {
+    let mut seen = HashSet::new();
+    paths
+        .into_iter()
+        .filter(|result| match result {
+            Ok(path) => seen.insert(fs::canonicalize(path).unwrap_or_else(|_| {
+                path.components()
+                    .filter(|c| c != &Component::CurDir)
+                    .collect()
+            })),
+            Err(_) => true,
+        })
+        .collect()
+}
+
+/// Lines of a file that should be formatted, parsed from `[FILE:]START[-END]`
+#[derive(Clone, Debug)]
+pub struct FileLines {
+    /// The file the lines belong to, or `None` when the lines apply to every file
+    pub file: Option<PathBuf>,
+    pub lines: RangeInclusive<usize>,
+}
+
+impl FromStr for FileLines {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (file, lines) = match s.rsplit_once(':') {
+            Some((file, lines)) => (Some(PathBuf::from(file)), lines),
+            None => (None, s),
+        };
+
+        let parse_line = |line: &str| match line.trim().parse::<usize>() {
+            Ok(line) if line > 0 => Ok(line),
+            _ => Err(format!("invalid line number `{line}`, lines start at 1")),
+        };
+
+        let lines = match lines.split_once('-') {
+            Some((start, end)) => parse_line(start)?..=parse_line(end)?,
+            None => parse_line(lines)?..=parse_line(lines)?,
+        };
+
+        if lines.is_empty() {
+            return Err(format!(
+                "invalid line range {}-{}, the start is after the end",
+                lines.start(),
+                lines.end()
+            ));
+        }
+
+        Ok(Self { file, lines })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_list_separated_by_newlines() {
+        assert_eq!(
+            parse_file_list("a.rs\nsrc/b.rs\n\nwith space.rs\n"),
+            ["a.rs", "src/b.rs", "with space.rs"].map(PathBuf::from)
+        );
+        assert_eq!(
+            parse_file_list("a.rs\r\nb.rs\r\n"),
+            ["a.rs", "b.rs"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn file_list_separated_by_nul() {
+        assert_eq!(
+            parse_file_list("a.rs\0line\nbreak.rs\0b.rs\0"),
+            ["a.rs", "line\nbreak.rs", "b.rs"].map(PathBuf::from)
+        );
+    }
+
+    fn excludes() -> Excludes {
+        let resolver = Resolver::new(None, Vec::new()).unwrap();
+        Excludes::new(&[], Arc::new(resolver)).unwrap()
+    }
+
+    #[test]
+    fn invalid_glob_pattern() {
+        let files = collect_files("src/[.rs", &excludes());
+        assert_eq!(files.len(), 1);
+
+        let err = files.into_iter().next().unwrap().unwrap_err();
+        assert_eq!(err.path, Path::new("src/[.rs"));
+        assert!(err.error.to_string().starts_with("invalid glob pattern"));
+    }
+
+    #[test]
+    fn dedup_paths() {
+        let paths = vec![
+            Ok(PathBuf::from("./a.rs")),
+            Ok(PathBuf::from("src/b.rs")),
+            Err(anyhow::anyhow!("first error")),
+            Ok(PathBuf::from("a.rs")),
+            Ok(PathBuf::from("./src/./b.rs")),
+            Err(anyhow::anyhow!("second error")),
+        ];
+
+        let paths: Vec<_> = dedup(paths)
+            .into_iter()
+            .map(|result| result.map_err(|err| err.to_string()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                Ok(PathBuf::from("./a.rs")),
+                Ok(PathBuf::from("src/b.rs")),
+                Err("first error".to_owned()),
+                Err("second error".to_owned()),
+            ]
+        );
+    }
+
+    /// The files found by walking `dir`, relative to `dir`
+    fn walk(dir: &Path) -> Vec<PathBuf> {
+        walk_dir(dir, &excludes())
+            .into_iter()
+            .map(|path| path.unwrap().strip_prefix(dir).unwrap().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn walk_dir_is_sorted() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "m9.rs", "m4.rs", "m19.rs", "b/a.rs", "a/z.rs", "a/b.rs", "c.txt",
+        ] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        assert_eq!(
+            walk(dir.path()),
+            ["a/b.rs", "a/z.rs", "b/a.rs", "m19.rs", "m4.rs", "m9.rs"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn ignore_of_nested_config() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, contents) in [
+            ("leptosfmt.toml", "ignore = [\"*_bindings.rs\"]"),
+            ("a_bindings.rs", ""),
+            ("main.rs", ""),
+            (
+                "crates/legacy/leptosfmt.toml",
+                "ignore = [\"/gen/\", \"old.rs\"]",
+            ),
+            ("crates/legacy/gen/a.rs", ""),
+            ("crates/legacy/src/old.rs", ""),
+            ("crates/legacy/src/new.rs", ""),
+            // the innermost `ignore` replaces the outer one, like every other setting
+            ("crates/legacy/b_bindings.rs", ""),
+            ("crates/other/gen/a.rs", ""),
+            ("crates/other/old.rs", ""),
+            ("crates/other/c_bindings.rs", ""),
+        ] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        assert_eq!(
+            walk(dir.path()),
+            [
+                "crates/legacy/b_bindings.rs",
+                "crates/legacy/src/new.rs",
+                "crates/other/gen/a.rs",
+                "crates/other/old.rs",
+                "main.rs",
+            ]
+            .map(PathBuf::from)
+        );
+    }
+
+    fn file_lines(s: &str) -> Result<(Option<PathBuf>, RangeInclusive<usize>), String> {
+        s.parse::<FileLines>()
+            .map(|file_lines| (file_lines.file, file_lines.lines))
+    }
+
+    #[test]
+    fn parse_file_lines() {
+        assert_eq!(
+            file_lines("src/main.rs:3-7"),
+            Ok((Some(PathBuf::from("src/main.rs")), 3..=7))
+        );
+        assert_eq!(file_lines("4"), Ok((None, 4..=4)));
+        assert_eq!(file_lines("2-2"), Ok((None, 2..=2)));
+        assert_eq!(
+            file_lines(r"C:\x.rs:1-2"),
+            Ok((Some(PathBuf::from(r"C:\x.rs")), 1..=2))
+        );
+    }
+
+    #[test]
+    fn invalid_file_lines() {
+        assert_eq!(
+            file_lines("0"),
+            Err("invalid line number `0`, lines start at 1".to_owned())
+        );
+        assert_eq!(
+            file_lines("a.rs:1-x"),
+            Err("invalid line number `x`, lines start at 1".to_owned())
+        );
+        assert_eq!(
+            file_lines("a.rs:5-3"),
+            Err("invalid line range 5-3, the start is after the end".to_owned())
+        );
+    }
+
+    #[test]
+    fn dedup_canonical_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("a.rs"), "").unwrap();
+        let absolute = dir.path().join("a.rs");
+        let relative = dir.path().join("src/../a.rs");
+
+        let paths: Vec<Result<_, ()>> = vec![Ok(relative.clone()), Ok(absolute)];
+        assert_eq!(dedup(paths), vec![Ok(relative)]);
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    any::Any,
+    panic,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Once,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Time after which formatting a single file is given up by default
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+const THREAD_NAME: &str = "leptosfmt-format";
+
+/// The formatter recurses into nested view macros and expressions, so it gets the stack size
+/// of the main thread instead of the 2 MiB default of spawned threads
+const STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Runs `f` on its own thread, turning a panic into an error and giving up after `timeout`.
+///
+/// A thread can't be stopped from the outside, so after a timeout it keeps running
+/// in the background until it finishes or the process exits.
+pub fn isolated<T: Send + 'static>(
+    timeout: Option<Duration>,
+    f: impl FnOnce() -> T + Send + 'static,
+) -> anyhow::Result<T> {
+    silence_panics();
+
+    let (tx, rx) = mpsc::channel();
+    let handle = thread::Builder::new()
+        .name(THREAD_NAME.to_owned())
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            let _ = tx.send(f());
+        })?;
+
+    let result = match timeout {
+        Some(timeout) => rx.recv_timeout(timeout),
+        None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    };
+
+    match result {
+        Ok(value) => Ok(value),
+        Err(RecvTimeoutError::Timeout) => anyhow::bail!(
+            "formatting took longer than {}s",
+            timeout.unwrap_or_default().as_secs_f32()
+        ),
+        Err(RecvTimeoutError::Disconnected) => match handle.join() {
+            Err(payload) => anyhow::bail!("formatting panicked: {}", panic_message(&*payload)),
+            Ok(()) => anyhow::bail!("formatting stopped without a result"),
+        },
+    }
+}
+
+/// Keeps the default panic hook from printing the panics of isolated threads,
+/// as they are reported as errors of the file instead
+fn silence_panics() {
+    static SILENCE_PANICS: Once = Once::new();
+    SILENCE_PANICS.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if thread::current().name() != Some(THREAD_NAME) {
+                default_hook(info);
+            }
+        }));
+    });
+}
+
+/// The message of a panic, its payload is a `String` or `&str` when created with `panic!`
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else {
+        "unknown cause"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn result() {
+        assert_eq!(isolated(None, || 42).unwrap(), 42);
+    }
+
+    #[test]
+    fn panic_with_literal() {
+        let err = isolated(None, || panic!("literal")).unwrap_err();
+        assert_eq!(err.to_string(), "formatting panicked: literal");
+    }
+
+    #[test]
+    fn panic_with_formatted_message() {
+        let err = isolated(None, || panic!("formatted {}", 42)).unwrap_err();
+        assert_eq!(err.to_string(), "formatting panicked: formatted 42");
+    }
+
+    #[test]
+    #[allow(clippy::empty_loop)]
+    fn timeout() {
+        let err = isolated(Some(Duration::from_millis(50)), || loop {}).unwrap_err();
+        assert_eq!(err.to_string(), "formatting took longer than 0.05s");
+    }
+}
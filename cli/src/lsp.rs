@@ -0,0 +1,221 @@
+use std::{collections::HashMap, env, ops::RangeInclusive, path::Path};
+
+use leptosfmt_formatter::{
+    check_edits, format_file_edits, format_file_edits_in_lines, FormatError, FormatterSettings,
+};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as NotificationTrait,
+    },
+    request::{Formatting, RangeFormatting, Request as RequestTrait},
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DocumentFormattingParams, DocumentRangeFormattingParams, OneOf, Position, Range,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+};
+
+use crate::isolate::{isolated, DEFAULT_TIMEOUT};
+
+/// Runs a language server over stdio that formats the view macros of the open documents.
+///
+/// `settings_for` resolves the settings for the directory of a document.
+pub fn run(
+    settings_for: impl Fn(&Path) -> anyhow::Result<FormatterSettings>,
+) -> anyhow::Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = serde_json::to_value(ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    })?;
+    connection.initialize(capabilities)?;
+
+    let mut documents: HashMap<Url, String> = HashMap::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+
+                let response = handle_request(request, &documents, &settings_for);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                handle_notification(notification, &mut documents)
+            }
+            Message::Response(_) => {}
+        }
+    }
+
+    // the writer thread only stops once the connection is dropped
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn handle_request(
+    request: Request,
+    documents: &HashMap<Url, String>,
+    settings_for: &impl Fn(&Path) -> anyhow::Result<FormatterSettings>,
+) -> Response {
+    let id = request.id.clone();
+
+    let result = match request.method.as_str() {
+        Formatting::METHOD => request
+            .extract::<DocumentFormattingParams>(Formatting::METHOD)
+            .map(|(_, params)| (params.text_document.uri, None)),
+        RangeFormatting::METHOD => request
+            .extract::<DocumentRangeFormattingParams>(RangeFormatting::METHOD)
+            .map(|(_, params)| (params.text_document.uri, Some(params.range))),
+        _ => {
+            return Response::new_err(
+                id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unsupported request: {}", request.method),
+            )
+        }
+    };
+
+    let (uri, range) = match result {
+        Ok(params) => params,
+        Err(err) => {
+            return Response::new_err(id, ErrorCode::InvalidParams as i32, format!("{err:?}"))
+        }
+    };
+
+    match format_document(&uri, range, documents, settings_for) {
+        Ok(edits) => Response::new_ok(id, edits),
+        Err(err) => Response::new_err(id, ErrorCode::RequestFailed as i32, format!("{err:#}")),
+    }
+}
+
+fn handle_notification(notification: Notification, documents: &mut HashMap<Url, String>) {
+    match notification.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            if let Ok(params) =
+                notification.extract::<DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD)
+            {
+                documents.insert(params.text_document.uri, params.text_document.text);
+            }
+        }
+        DidChangeTextDocument::METHOD => {
+            if let Ok(mut params) =
+                notification.extract::<DidChangeTextDocumentParams>(DidChangeTextDocument::METHOD)
+            {
+                // with full synchronization the last change contains the whole document
+                if let Some(change) = params.content_changes.pop() {
+                    documents.insert(params.text_document.uri, change.text);
+                }
+            }
+        }
+        DidCloseTextDocument::METHOD => {
+            if let Ok(params) =
+                notification.extract::<DidCloseTextDocumentParams>(DidCloseTextDocument::METHOD)
+            {
+                documents.remove(&params.text_document.uri);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Formats the view macros of a document, or only those intersecting with `range`,
+/// returning an edit for every macro that changed
+fn format_document(
+    uri: &Url,
+    range: Option<Range>,
+    documents: &HashMap<Url, String>,
+    settings_for: &impl Fn(&Path) -> anyhow::Result<FormatterSettings>,
+) -> anyhow::Result<Option<Vec<TextEdit>>> {
+    let path = uri.to_file_path().ok();
+    let source = match (documents.get(uri), &path) {
+        (Some(source), _) => source.clone(),
+        (None, Some(path)) => std::fs::read_to_string(path)?,
+        (None, None) => anyhow::bail!("unknown document: {uri}"),
+    };
+
+    let dir = match path.as_deref().and_then(Path::parent) {
+        Some(dir) => dir.to_owned(),
+        None => env::current_dir()?,
+    };
+    let settings = settings_for(&dir)?;
+
+    let edits = isolated(Some(DEFAULT_TIMEOUT), {
+        let source = source.clone();
+        move || {
+            let edits = match range {
+                Some(range) => format_file_edits_in_lines(&source, settings, &[lines(range)]),
+                None => format_file_edits(&source, settings),
+            }?;
+            check_edits(&source, &edits)?;
+            Ok::<_, FormatError>(edits)
+        }
+    })??;
+
+    Ok(Some(
+        edits
+            .into_iter()
+            .filter(|edit| source[edit.range.clone()] != edit.new_text)
+            .map(|edit| TextEdit {
+                range: Range {
+                    start: position(&source, edit.range.start),
+                    end: position(&source, edit.range.end),
+                },
+                new_text: edit.new_text,
+            })
+            .collect(),
+    ))
+}
+
+/// The 1-based lines of an LSP range. A range ending at the start of a line, like when whole lines are
+/// selected, doesn't include that line.
+fn lines(range: Range) -> RangeInclusive<usize> {
+    let end = match range.end {
+        Position { line, character: 0 } if line > range.start.line => line - 1,
+        Position { line, .. } => line,
+    };
+    range.start.line as usize + 1..=end as usize + 1
+}
+
+/// Converts a byte offset to an LSP position, of which the character is counted in UTF-16 code units
+fn position(source: &str, offset: usize) -> Position {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        Range {
+            start: Position::new(start.0, start.1),
+            end: Position::new(end.0, end.1),
+        }
+    }
+
+    #[test]
+    fn range_lines() {
+        assert_eq!(lines(range((0, 0), (0, 0))), 1..=1);
+        assert_eq!(lines(range((2, 4), (5, 1))), 3..=6);
+        // selecting whole lines ends the range at the start of the next line
+        assert_eq!(lines(range((2, 0), (5, 0))), 3..=5);
+    }
+
+    #[test]
+    fn utf16_position() {
+        let source = "a\nä𝄞b";
+        assert_eq!(position(source, 0), Position::new(0, 0));
+        assert_eq!(position(source, 2), Position::new(1, 0));
+        assert_eq!(position(source, source.len()), Position::new(1, 4));
+    }
+}
@@ -1,20 +1,75 @@
 use std::{
-    env, fs, panic,
+    collections::HashSet,
+    env,
+    ffi::OsString,
+    fs,
+    io::{self, IsTerminal, Read, Write},
+    num::NonZeroUsize,
+    ops::RangeInclusive,
     path::{Path, PathBuf},
-    time::Instant,
+    process,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
-use clap::Parser;
-use glob::glob;
-use leptosfmt_formatter::{format_file, FormatterSettings};
-use rayon::{iter::ParallelIterator, prelude::IntoParallelIterator};
+use cache::Cache;
+use clap::{Parser, Subcommand, ValueEnum};
+use config::{Override, Resolver, CONFIG_FILE};
+use diagnostic::Diagnostic;
+use leptosfmt_formatter::{
+    apply_edits, check_edits, format_file_edits, format_file_edits_in_lines, FormatError,
+    FormatterSettings,
+};
+use rayon::{
+    iter::{IndexedParallelIterator, ParallelIterator},
+    prelude::IntoParallelIterator,
+};
+use report::{ErrorReport, FileReport, MessageFormat, Region, Status};
+
+mod cache;
+mod config;
+mod diagnostic;
+mod diff;
+mod editorconfig;
+mod git;
+mod init;
+mod input;
+mod isolate;
+mod lsp;
+mod report;
+mod rustfmt;
+mod schema;
+mod verify;
+mod watch;
+
+/// Exit code used when `--check` finds files that are not formatted
+const EXIT_NEEDS_FORMATTING: i32 = 1;
+
+/// Exit code used when one or more files could not be formatted
+const EXIT_ERROR: i32 = 2;
 
 /// A formatter for Leptos RSX sytnax
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    /// A file, directory or glob
-    input_pattern: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Files, directories or globs
+    #[arg(required_unless_present_any = ["stdin", "files_from", "changed_since", "lines", "print_config"])]
+    input_patterns: Vec<String>,
+
+    /// Read the paths of the files to format from a file, or from stdin when `-`.
+    /// Paths are separated by newlines or NUL characters
+    #[arg(long, value_name = "PATH")]
+    files_from: Option<PathBuf>,
+
+    /// Only format files that changed compared to a git revision, including staged and untracked files.
+    /// Without inputs, the changed files of the whole repository are formatted, not only those in the current directory.
+    /// When combined with inputs, only the changed files among them are formatted
+    #[arg(long, value_name = "REV")]
+    changed_since: Option<String>,
 
     // Maximum width of each line
     #[arg(short, long)]
@@ -27,97 +82,658 @@ struct Args {
     // Config file
     #[arg(short, long)]
     config_file: Option<PathBuf>,
+
+    /// Override a setting of the config files, e.g. `--config attr_value_brace_style=Always`.
+    /// Can be repeated, strings don't need to be quoted
+    #[arg(long = "config", value_name = "KEY=VALUE")]
+    config_overrides: Vec<Override>,
+
+    /// Print the effective settings of the current directory, or of the first input,
+    /// with the source of every value and exit
+    #[arg(long, conflicts_with_all = ["stdin", "watch"])]
+    print_config: bool,
+
+    /// Only format the view macros that intersect with these lines, can be repeated.
+    /// Lines without a file apply to all files, files with lines are formatted when no other inputs are given
+    #[arg(long, value_name = "[FILE:]START-END", conflicts_with_all = ["rustfmt", "watch"])]
+    lines: Vec<input::FileLines>,
+
+    /// Gitignore-style pattern of files to skip, can be repeated
+    #[arg(short, long)]
+    exclude: Vec<String>,
+
+    /// Check if the files are formatted without writing them.
+    /// Exits with 1 if a file needs formatting and with 2 if a file could not be formatted
+    #[arg(long)]
+    check: bool,
+
+    /// Format stdin and write the result to stdout
+    #[arg(long, conflicts_with_all = ["input_patterns", "files_from", "changed_since"])]
+    stdin: bool,
+
+    /// Path of the file that is read from stdin, used to discover the config file
+    #[arg(long, requires = "stdin")]
+    stdin_filepath: Option<PathBuf>,
+
+    /// Print a unified diff of the changes instead of writing the files
+    #[arg(long)]
+    diff: bool,
+
+    /// When to use colors in the diff output and error messages
+    #[arg(long, value_enum, default_value_t = Color::Auto)]
+    color: Color,
+
+    /// Format the code with rustfmt before formatting the view macros.
+    /// Use together with --stdin as rust-analyzer's `rustfmt.overrideCommand`
+    #[arg(long)]
+    rustfmt: bool,
+
+    /// Output format of the report, the machine readable formats are written to stdout
+    #[arg(long, value_enum, default_value_t = MessageFormat::Human, conflicts_with = "diff")]
+    message_format: MessageFormat,
+
+    /// Watch the input directories and format files containing view macros when they are saved
+    #[arg(long, conflicts_with_all = ["check", "diff", "files_from", "changed_since", "message_format"])]
+    watch: bool,
+
+    /// Don't skip files that were already formatted in a previous run.
+    /// The cache is stored in `target/leptosfmt-cache` of the Cargo workspace
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Number of files to format in parallel, defaults to the number of CPUs.
+    /// With 1 the files are formatted and reported in order
+    #[arg(short, long, env = "LEPTOSFMT_JOBS", value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+
+    /// Don't check that formatting keeps the tokens of the view macros the same
+    /// before writing a file, apart from whitespace, trailing commas and the braces it adds or removes
+    #[arg(long)]
+    no_equivalence_check: bool,
+
+    /// Give up formatting a file after this many seconds, 0 disables the timeout.
+    /// The file is still formatted in the background, so a timeout can exceed the number of jobs
+    #[arg(long, value_name = "SECONDS", default_value_t = isolate::DEFAULT_TIMEOUT.as_secs())]
+    timeout: u64,
+
+    /// Format every file twice and don't write it if the second pass changes a view macro again
+    #[arg(long)]
+    verify: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Start a language server on stdio that supports formatting and range formatting
+    Lsp,
+    /// Write a leptosfmt.toml with the default settings commented out to the current directory
+    Init {
+        /// Overwrite an existing leptosfmt.toml
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print a JSON Schema of leptosfmt.toml, e.g. for completion in editors with taplo
+    Schema,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+impl Args {
+    fn writes_files(&self) -> bool {
+        !(self.check || self.diff)
+    }
+
+    /// The lines to format in `file`, or `None` to format the whole file
+    fn lines_for(&self, file: Option<&Path>) -> Option<Vec<RangeInclusive<usize>>> {
+        if self.lines.is_empty() {
+            return None;
+        }
+
+        let file = file.and_then(|file| fs::canonicalize(file).ok());
+        Some(
+            self.lines
+                .iter()
+                .filter(|lines| match &lines.file {
+                    Some(lines_file) => file.is_some() && fs::canonicalize(lines_file).ok() == file,
+                    None => true,
+                })
+                .map(|lines| lines.lines.clone())
+                .collect(),
+        )
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        (self.timeout > 0).then(|| Duration::from_secs(self.timeout))
+    }
+
+    fn colored(&self, stream: &impl IsTerminal) -> bool {
+        match self.color {
+            Color::Auto => stream.is_terminal() && env::var_os("NO_COLOR").is_none(),
+            Color::Always => true,
+            Color::Never => false,
+        }
+    }
+}
+
+/// The formatted source and the view macros that were changed
+struct Formatted {
+    source: String,
+    changed_macros: Vec<Region>,
 }
 
 fn main() {
     let args = Args::parse();
 
-    let settings = match settings(&args) {
-        Ok(settings) => settings,
-        Err(err) => {
-            eprintln!("{}", err);
+    match args.command {
+        Some(Command::Lsp) => {
+            // resolve the config for every request, so changes to the config files are picked up
+            if let Err(err) = lsp::run(|dir| resolver(&args)?.settings_for(dir)) {
+                eprintln!("{}", err);
+                process::exit(EXIT_ERROR);
+            }
             return;
         }
+        Some(Command::Init { force }) => {
+            if let Err(err) = init::init(Path::new(CONFIG_FILE), force) {
+                eprintln!("{:#}", err);
+                process::exit(EXIT_ERROR);
+            }
+            println!("Created {}", CONFIG_FILE);
+            return;
+        }
+        Some(Command::Schema) => {
+            println!("{:#}", schema::schema());
+            return;
+        }
+        None => {}
+    }
+
+    let resolver = match resolver(&args) {
+        Ok(resolver) => Arc::new(resolver),
+        Err(err) => {
+            eprintln!("{:#}", err);
+            process::exit(EXIT_ERROR);
+        }
     };
 
-    let is_dir = fs::metadata(&args.input_pattern)
-        .map(|meta| meta.is_dir())
-        .unwrap_or(false);
+    if args.print_config {
+        match print_config_dir(&args)
+            .map_err(anyhow::Error::from)
+            .and_then(|dir| resolver.describe(&dir))
+        {
+            Ok(description) => print!("{}", description),
+            Err(err) => {
+                eprintln!("{:#}", err);
+                process::exit(EXIT_ERROR);
+            }
+        }
+        return;
+    }
 
-    let glob_pattern = if is_dir {
-        format!("{}/**/*.rs", &args.input_pattern)
-    } else {
-        args.input_pattern
+    // the config of the current directory, or of the file read from stdin
+    let config = match start_dir(&args)
+        .map_err(anyhow::Error::from)
+        .and_then(|dir| resolver.config_for(&dir))
+    {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{:#}", err);
+            process::exit(EXIT_ERROR);
+        }
     };
 
-    let file_paths: Vec<_> = glob(&glob_pattern)
-        .expect("failed to read glob pattern")
+    if args.stdin {
+        if let Err(err) = format_stdin(config.settings, &args) {
+            match err.downcast_ref::<Diagnostic>() {
+                Some(diagnostic) => {
+                    let path = args.stdin_filepath.as_deref();
+                    let path = path.unwrap_or(Path::new("<stdin>"));
+                    eprint!("{}", diagnostic.render(path, args.colored(&io::stderr())));
+                }
+                None => eprintln!("{:#}", err),
+            }
+            process::exit(EXIT_ERROR);
+        }
+        return;
+    }
+
+    let excludes = match input::Excludes::new(&args.exclude, resolver.clone()) {
+        Ok(excludes) => excludes,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(EXIT_ERROR);
+        }
+    };
+
+    if args.watch {
+        if let Err(err) = watch_dirs(&resolver, &args, &excludes) {
+            eprintln!("{}", err);
+            process::exit(EXIT_ERROR);
+        }
+        return;
+    }
+
+    let mut file_paths: Vec<_> = args
+        .input_patterns
+        .iter()
+        .flat_map(|input| input::collect_files(input, &excludes))
         .collect();
 
-    let total_files = file_paths.len();
-    let start_formatting = Instant::now();
-    file_paths.into_par_iter().for_each(|result| {
-        let print_err = |path: &Path, err| {
-            println!("❌ {}", path.display());
-            eprintln!("\t\t{}", err);
-        };
+    if args.input_patterns.is_empty() && args.files_from.is_none() && args.changed_since.is_none() {
+        file_paths.extend(
+            args.lines
+                .iter()
+                .filter_map(|lines| lines.file.clone())
+                .map(Ok),
+        );
+    }
+
+    if let Some(files_from) = &args.files_from {
+        match input::read_file_list(files_from) {
+            Ok(paths) => file_paths.extend(
+                paths
+                    .into_iter()
+                    .filter(|path| !excludes.is_excluded(path, false))
+                    .map(Ok),
+            ),
+            Err(err) => {
+                eprintln!("could not read {}: {}", files_from.display(), err);
+                process::exit(EXIT_ERROR);
+            }
+        }
+    }
 
-        match result {
-            Ok(path) => match format_glob_result(&path, settings) {
-                Ok(_) => println!("✅ {}", path.display()),
-                Err(err) => print_err(&path, &err.to_string()),
-            },
-            Err(err) => print_err(err.path(), &err.error().to_string()),
+    if let Some(rev) = &args.changed_since {
+        let changed_files = match git::changed_files(rev) {
+            Ok(files) => files,
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(EXIT_ERROR);
+            }
         };
-    });
+
+        if args.input_patterns.is_empty() && args.files_from.is_none() {
+            file_paths = changed_files
+                .into_iter()
+                .filter(|path| !excludes.is_excluded(path, false))
+                .map(Ok)
+                .collect();
+        } else {
+            let changed_files: HashSet<_> = changed_files
+                .iter()
+                .filter_map(|path| fs::canonicalize(path).ok())
+                .collect();
+            file_paths.retain(|result| match result {
+                Ok(path) => fs::canonicalize(path).is_ok_and(|path| changed_files.contains(&path)),
+                Err(_) => true,
+            });
+        }
+    }
+
+    let file_paths = input::dedup(file_paths);
+
+    // rustfmt may have its own configuration, which isn't part of the cache key,
+    // and a partially formatted file can't be stored as formatted
+    let cache = (!args.no_cache && !args.rustfmt && args.lines.is_empty())
+        .then(Cache::default_dir)
+        .flatten()
+        .map(Cache::open);
+
+    if let Some(jobs) = args.jobs {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs.get());
+        if let Err(err) = pool.build_global() {
+            eprintln!("{}", err);
+            process::exit(EXIT_ERROR);
+        }
+    }
+
+    let total_files = file_paths.len();
+    let human = args.message_format == MessageFormat::Human;
+    let start_formatting = Instant::now();
+    // a single chunk is formatted in order, while rayon could otherwise still split the files
+    let min_len = match args.jobs {
+        Some(jobs) if jobs.get() == 1 => total_files.max(1),
+        _ => 1,
+    };
+    let reports: Vec<_> = file_paths
+        .into_par_iter()
+        .with_min_len(min_len)
+        .map(|result| {
+            let report = match result {
+                Ok(path) => format_glob_result(&path, &resolver, &args, cache.as_ref()),
+                Err(err) => FileReport::error(err.path, &err.error),
+            };
+            if human {
+                print_report(&report, &args);
+            }
+            report
+        })
+        .collect();
     let end_formatting = Instant::now();
-    println!(
-        "Formatted {} files in {} ms",
-        total_files,
-        (end_formatting - start_formatting).as_millis()
-    )
+
+    if let Some(cache) = cache {
+        if let Err(err) = cache.save() {
+            eprintln!("could not save the cache: {}", err);
+        }
+    }
+
+    let count = |status| reports.iter().filter(|r| r.status == status).count();
+    let failed_files = count(Status::Error);
+    let changed_files = count(Status::Changed);
+
+    if !human {
+        print!("{}", report::render(args.message_format, &reports));
+    } else if args.check {
+        println!(
+            "Checked {} files in {} ms, {} need formatting",
+            total_files,
+            (end_formatting - start_formatting).as_millis(),
+            changed_files
+        );
+    } else if args.writes_files() {
+        println!(
+            "Formatted {} files in {} ms, {} changed, {} unchanged",
+            total_files,
+            (end_formatting - start_formatting).as_millis(),
+            changed_files,
+            count(Status::Unchanged)
+        );
+    }
+
+    if failed_files > 0 {
+        process::exit(EXIT_ERROR);
+    } else if args.check && changed_files > 0 {
+        process::exit(EXIT_NEEDS_FORMATTING);
+    }
 }
 
-fn settings(args: &Args) -> anyhow::Result<FormatterSettings> {
-    let mut settings: FormatterSettings =
-        if let Some(config_file) = args.config_file.clone().or_else(find_config) {
-            fs::read_to_string(config_file).map(|s| toml::from_str(&s))??
-        } else {
-            FormatterSettings::default()
-        };
+fn print_report(report: &FileReport, args: &Args) {
+    let path = report.file.display();
+    match report.status {
+        Status::Changed if args.writes_files() => println!("✅ {}", path),
+        Status::Changed if args.check => println!("⚠️ {}", path),
+        Status::Changed | Status::Unchanged => {}
+        Status::Error => {
+            println!("❌ {}", path);
+            match &report.error {
+                Some(ErrorReport {
+                    diagnostic: Some(diagnostic),
+                    ..
+                }) => eprint!(
+                    "{}",
+                    diagnostic.render(&report.file, args.colored(&io::stderr()))
+                ),
+                Some(error) => eprintln!("\t\t{}", error.message),
+                None => {}
+            }
+        }
+    }
+}
 
-    if let Some(max_width) = args.max_width {
-        settings.max_width = max_width;
+fn watch_dirs(resolver: &Resolver, args: &Args, excludes: &input::Excludes) -> anyhow::Result<()> {
+    let dirs: Vec<_> = args.input_patterns.iter().map(PathBuf::from).collect();
+    if let Some(dir) = dirs.iter().find(|dir| !dir.is_dir()) {
+        anyhow::bail!(
+            "can only watch directories, {} is not a directory",
+            dir.display()
+        );
     }
 
+    println!("Watching for changes, press Ctrl+C to stop");
+    let current_dir = env::current_dir()?;
+    watch::watch(&dirs, |changed| {
+        // only format the files that would be formatted when walking the directories
+        let formattable: HashSet<_> = dirs
+            .iter()
+            .flat_map(|dir| input::collect_files(&dir.to_string_lossy(), excludes))
+            .filter_map(|result| fs::canonicalize(result.ok()?).ok())
+            .collect();
+
+        for path in changed {
+            let is_formattable =
+                fs::canonicalize(&path).is_ok_and(|path| formattable.contains(&path));
+            let has_view_macro =
+                fs::read_to_string(&path).is_ok_and(|source| source.contains("view!"));
+            if !(is_formattable && has_view_macro) {
+                continue;
+            }
+
+            let path = path.strip_prefix(&current_dir).unwrap_or(&path);
+            print_report(&format_glob_result(path, resolver, args, None), args);
+        }
+    })
+}
+
+/// Resolves the config of every directory from the config files and the overrides on the command line
+fn resolver(args: &Args) -> anyhow::Result<Resolver> {
+    let mut overrides = args.config_overrides.clone();
+    if let Some(max_width) = args.max_width {
+        overrides.push(Override::new("max_width", max_width as i64, "--max-width"));
+    }
     if let Some(tab_spaces) = args.tab_spaces {
-        settings.tab_spaces = tab_spaces;
+        overrides.push(Override::new(
+            "tab_spaces",
+            tab_spaces as i64,
+            "--tab-spaces",
+        ));
+    }
+
+    Resolver::new(args.config_file.clone(), overrides)
+}
+
+/// The directory of the file read from stdin, or the current directory
+fn start_dir(args: &Args) -> io::Result<PathBuf> {
+    match &args.stdin_filepath {
+        Some(path) => parent_dir(path),
+        None => env::current_dir(),
     }
+}
 
-    Ok(settings)
+/// The directory of the first input, or the current directory
+fn print_config_dir(args: &Args) -> io::Result<PathBuf> {
+    match args.input_patterns.first().map(Path::new) {
+        Some(input) if input.is_dir() => fs::canonicalize(input),
+        Some(input) => parent_dir(input),
+        None => env::current_dir(),
+    }
 }
 
-fn find_config() -> Option<PathBuf> {
-    let mut path: PathBuf = env::current_dir().ok()?;
-    let file = Path::new("leptosfmt.toml");
+/// Returns the absolute path of the directory containing `path`, which is canonicalized
+/// if it exists, so walking up its parents doesn't depend on how `path` was written
+fn parent_dir(path: &Path) -> io::Result<PathBuf> {
+    let mut path = env::current_dir()?.join(path);
+    path.pop();
+    Ok(fs::canonicalize(&path).unwrap_or(path))
+}
 
-    loop {
-        path.push(file);
+fn format_glob_result(
+    file: &Path,
+    resolver: &Resolver,
+    args: &Args,
+    cache: Option<&Cache>,
+) -> FileReport {
+    let original = match fs::read_to_string(file) {
+        Ok(original) => original,
+        Err(err) => return FileReport::error(file.to_owned(), &err.into()),
+    };
 
-        if path.is_file() {
-            println!("Discovered config at {}", path.display());
-            break Some(path);
+    let dir = match parent_dir(file) {
+        Ok(dir) => dir,
+        Err(err) => return FileReport::error(file.to_owned(), &err.into()),
+    };
+    let settings = match resolver.settings_for(&dir) {
+        Ok(settings) => settings,
+        Err(err) => return FileReport::error(file.to_owned(), &err),
+    };
+
+    let cache_key = cache.map(|cache| cache.key(&original, settings));
+    if let (Some(cache), Some(key)) = (cache, cache_key) {
+        if cache.is_formatted(file, key) {
+            return FileReport {
+                file: file.to_owned(),
+                status: Status::Unchanged,
+                macros: Vec::new(),
+                error: None,
+            };
         }
+    }
 
-        if !(path.pop() && path.pop()) {
-            break None;
+    let lines = args.lines_for(Some(file));
+    let formatted = match format_source(&original, &dir, lines, settings, args) {
+        Ok(formatted) => formatted,
+        Err(err) => return FileReport::error(file.to_owned(), &err),
+    };
+
+    let status = if original == formatted.source {
+        if let (Some(cache), Some(key)) = (cache, cache_key) {
+            cache.insert(file, key);
+        }
+        Status::Unchanged
+    } else if !args.writes_files() {
+        if args.diff {
+            print!(
+                "{}",
+                diff::unified_diff(
+                    file,
+                    &original,
+                    &formatted.source,
+                    args.colored(&io::stdout())
+                )
+            );
         }
+        Status::Changed
+    } else if let Err(err) = write_atomically(file, &formatted.source) {
+        return FileReport::error(file.to_owned(), &err.into());
+    } else {
+        Status::Changed
+    };
+
+    FileReport {
+        file: file.to_owned(),
+        status,
+        macros: formatted.changed_macros,
+        error: None,
+    }
+}
+
+/// Writes to a temporary file next to `path` and renames it, so an interrupted write
+/// never leaves a truncated file behind. The permissions of the original file are kept.
+/// Every write gets its own temporary file, which is removed again when the write fails.
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let path = fs::canonicalize(path)?;
+    let permissions = fs::metadata(&path)?.permissions();
+
+    let mut prefix = OsString::from(".");
+    prefix.push(path.file_name().unwrap_or_default());
+    prefix.push(".leptosfmt-");
+    let mut temp_file = tempfile::Builder::new()
+        .prefix(&prefix)
+        .suffix(".tmp")
+        .tempfile_in(path.parent().unwrap_or(Path::new(".")))?;
+
+    temp_file.write_all(contents.as_bytes())?;
+    fs::set_permissions(temp_file.path(), permissions)?;
+    temp_file.persist(&path)?;
+    Ok(())
+}
+
+fn format_source(
+    source: &str,
+    dir: &Path,
+    lines: Option<Vec<RangeInclusive<usize>>>,
+    settings: FormatterSettings,
+    args: &Args,
+) -> anyhow::Result<Formatted> {
+    let source = source.to_owned();
+    // rustfmt runs isolated as well, so it is covered by the timeout and can't take down the other files
+    let rustfmt_dir = args.rustfmt.then(|| dir.to_owned());
+    let check_equivalence = !args.no_equivalence_check;
+    let verify = args.verify;
+    isolate::isolated(args.timeout(), move || {
+        let source = match rustfmt_dir {
+            Some(dir) => rustfmt::rustfmt(&source, &dir)?,
+            None => source,
+        };
+        format_macros(&source, lines, settings, check_equivalence, verify)
+    })?
+}
+
+/// Formats the view macros of `source`, which runs isolated from the other files
+fn format_macros(
+    source: &str,
+    lines: Option<Vec<RangeInclusive<usize>>>,
+    settings: FormatterSettings,
+    check_equivalence: bool,
+    verify: bool,
+) -> anyhow::Result<Formatted> {
+    let to_diagnostic = |err: FormatError| match Diagnostic::from_format_error(&err, source) {
+        Some(diagnostic) => anyhow::Error::from(diagnostic),
+        None => err.into(),
+    };
+
+    let edits = match lines {
+        Some(lines) => format_file_edits_in_lines(source, settings, &lines),
+        None => format_file_edits(source, settings),
+    }
+    .map_err(to_diagnostic)?;
+
+    if check_equivalence {
+        check_edits(source, &edits).map_err(to_diagnostic)?;
     }
+
+    let changed_macros = edits
+        .iter()
+        .filter(|edit| source[edit.range.clone()] != edit.new_text)
+        .map(|edit| Region::of_range(source, edit.range.clone()))
+        .collect();
+
+    let formatted = if verify {
+        let formatted = apply_edits(source, edits.clone());
+        verify::verify_stable(&formatted, &edits, settings)?;
+        formatted
+    } else {
+        apply_edits(source, edits)
+    };
+
+    Ok(Formatted {
+        source: formatted,
+        changed_macros,
+    })
 }
 
-fn format_glob_result(file: &PathBuf, settings: FormatterSettings) -> anyhow::Result<()> {
-    let formatted = panic::catch_unwind(|| format_file(file, settings))
-        .map_err(|e| anyhow::anyhow!(e.downcast::<String>().unwrap()))??;
-    fs::write(file, formatted)?;
+fn format_stdin(settings: FormatterSettings, args: &Args) -> anyhow::Result<()> {
+    let mut source = String::new();
+    io::stdin().read_to_string(&mut source)?;
+
+    let dir = match &args.stdin_filepath {
+        Some(path) => parent_dir(path)?,
+        None => env::current_dir()?,
+    };
+    let lines = args.lines_for(args.stdin_filepath.as_deref());
+    let formatted = format_source(&source, &dir, lines, settings, args)?.source;
+
+    if args.diff && source != formatted {
+        let path = args
+            .stdin_filepath
+            .as_deref()
+            .unwrap_or(Path::new("<stdin>"));
+        print!(
+            "{}",
+            diff::unified_diff(path, &source, &formatted, args.colored(&io::stdout()))
+        );
+    }
+
+    if !args.writes_files() {
+        if args.check && source != formatted {
+            process::exit(EXIT_NEEDS_FORMATTING);
+        }
+        return Ok(());
+    }
+
+    io::stdout().write_all(formatted.as_bytes())?;
     Ok(())
 }
@@ -0,0 +1,368 @@
+use std::{fmt::Write, ops::Range, path::PathBuf};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::diagnostic::Diagnostic;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum MessageFormat {
+    Human,
+    Json,
+    Sarif,
+    Checkstyle,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Changed,
+    Unchanged,
+    Error,
+}
+
+/// A 1-based line and column, the column is counted in chars
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn of_offset(source: &str, offset: usize) -> Self {
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Region {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Region {
+    pub fn of_range(source: &str, range: Range<usize>) -> Self {
+        Self {
+            start: Position::of_offset(source, range.start),
+            end: Position::of_offset(source, range.end),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ErrorReport {
+    pub message: String,
+    pub location: Option<Position>,
+    #[serde(skip)]
+    pub diagnostic: Option<Diagnostic>,
+}
+
+impl From<&anyhow::Error> for ErrorReport {
+    fn from(err: &anyhow::Error) -> Self {
+        match err.downcast_ref::<Diagnostic>() {
+            Some(diagnostic) => Self {
+                message: diagnostic.to_string(),
+                location: Some(diagnostic.start),
+                diagnostic: Some(diagnostic.clone()),
+            },
+            None => Self {
+                message: format!("{err:#}"),
+                location: None,
+                diagnostic: None,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct FileReport {
+    pub file: PathBuf,
+    pub status: Status,
+    /// The view macros that (would) change
+    pub macros: Vec<Region>,
+    pub error: Option<ErrorReport>,
+}
+
+impl FileReport {
+    pub fn error(file: PathBuf, err: &anyhow::Error) -> Self {
+        Self {
+            file,
+            status: Status::Error,
+            macros: Vec::new(),
+            error: Some(err.into()),
+        }
+    }
+}
+
+/// Renders the reports of all files in a machine readable format
+pub fn render(format: MessageFormat, reports: &[FileReport]) -> String {
+    match format {
+        MessageFormat::Human => String::new(),
+        MessageFormat::Json => json(reports),
+        MessageFormat::Sarif => sarif(reports),
+        MessageFormat::Checkstyle => checkstyle(reports),
+    }
+}
+
+/// One JSON object per line and file, like `cargo --message-format json`
+fn json(reports: &[FileReport]) -> String {
+    reports
+        .iter()
+        .map(|report| serde_json::to_string(report).unwrap() + "\n")
+        .collect()
+}
+
+const UNFORMATTED_MESSAGE: &str = "view! macro is not formatted";
+
+fn sarif(reports: &[FileReport]) -> String {
+    let uri = |report: &FileReport| report.file.to_string_lossy().replace('\\', "/");
+
+    let results: Vec<_> = reports
+        .iter()
+        .flat_map(|report| {
+            let unformatted = report.macros.iter().map(|region| {
+                json!({
+                    "ruleId": "unformatted",
+                    "level": "warning",
+                    "message": { "text": UNFORMATTED_MESSAGE },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": uri(report) },
+                            "region": {
+                                "startLine": region.start.line,
+                                "startColumn": region.start.column,
+                                "endLine": region.end.line,
+                                "endColumn": region.end.column,
+                            }
+                        }
+                    }]
+                })
+            });
+
+            let error = report.error.iter().map(|error| {
+                let mut location = json!({ "artifactLocation": { "uri": uri(report) } });
+                if let Some(position) = error.location {
+                    location["region"] = json!({
+                        "startLine": position.line,
+                        "startColumn": position.column,
+                    });
+                }
+
+                json!({
+                    "ruleId": "error",
+                    "level": "error",
+                    "message": { "text": error.message },
+                    "locations": [{ "physicalLocation": location }]
+                })
+            });
+
+            unformatted.chain(error).collect::<Vec<_>>()
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "leptosfmt",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": [
+                        {
+                            "id": "unformatted",
+                            "shortDescription": { "text": UNFORMATTED_MESSAGE }
+                        },
+                        {
+                            "id": "error",
+                            "shortDescription": { "text": "file could not be formatted" }
+                        }
+                    ]
+                }
+            },
+            "results": results
+        }]
+    });
+
+    serde_json::to_string_pretty(&log).unwrap() + "\n"
+}
+
+fn checkstyle(reports: &[FileReport]) -> String {
+    let mut output = String::new();
+    writeln!(output, r#"<?xml version="1.0" encoding="utf-8"?>"#).unwrap();
+    writeln!(output, r#"<checkstyle version="4.3">"#).unwrap();
+
+    for report in reports {
+        let name = escape_xml(&report.file.to_string_lossy());
+        writeln!(output, r#"  <file name="{name}">"#).unwrap();
+
+        for region in &report.macros {
+            writeln!(
+                output,
+                r#"    <error line="{}" column="{}" severity="warning" message="{}" source="leptosfmt.unformatted" />"#,
+                region.start.line, region.start.column, UNFORMATTED_MESSAGE
+            )
+            .unwrap();
+        }
+
+        if let Some(error) = &report.error {
+            let (line, column) = error
+                .location
+                .map_or((0, 0), |position| (position.line, position.column));
+            writeln!(
+                output,
+                r#"    <error line="{}" column="{}" severity="error" message="{}" source="leptosfmt.error" />"#,
+                line,
+                column,
+                escape_xml(&error.message)
+            )
+            .unwrap();
+        }
+
+        writeln!(output, "  </file>").unwrap();
+    }
+
+    writeln!(output, "</checkstyle>").unwrap();
+    output
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reports() -> Vec<FileReport> {
+        let position = |line, column| Position { line, column };
+        vec![
+            FileReport {
+                file: PathBuf::from("src/app.rs"),
+                status: Status::Changed,
+                macros: vec![Region {
+                    start: position(2, 5),
+                    end: position(4, 6),
+                }],
+                error: None,
+            },
+            FileReport {
+                file: PathBuf::from("src/lib.rs"),
+                status: Status::Unchanged,
+                macros: Vec::new(),
+                error: None,
+            },
+            FileReport {
+                file: PathBuf::from("src/invalid.rs"),
+                status: Status::Error,
+                macros: Vec::new(),
+                error: Some(ErrorReport {
+                    message: "could not parse view! macro: expected `<`".to_owned(),
+                    location: Some(position(3, 9)),
+                    diagnostic: None,
+                }),
+            },
+            FileReport::error(
+                PathBuf::from("src/unreadable"),
+                &anyhow::anyhow!("permission denied"),
+            ),
+        ]
+    }
+
+    #[test]
+    fn json_report() {
+        let output = render(MessageFormat::Json, &reports());
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            json!({
+                "file": "src/app.rs",
+                "status": "changed",
+                "macros": [{
+                    "start": { "line": 2, "column": 5 },
+                    "end": { "line": 4, "column": 6 },
+                }],
+                "error": null,
+            })
+        );
+        assert_eq!(lines[1]["status"], "unchanged");
+        assert_eq!(
+            lines[2]["error"],
+            json!({
+                "message": "could not parse view! macro: expected `<`",
+                "location": { "line": 3, "column": 9 },
+            })
+        );
+        assert_eq!(lines[3]["file"], "src/unreadable");
+        assert_eq!(lines[3]["status"], "error");
+        assert_eq!(lines[3]["error"]["location"], json!(null));
+    }
+
+    #[test]
+    fn sarif_report() {
+        let output = render(MessageFormat::Sarif, &reports());
+        let log: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["ruleId"], "unformatted");
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "src/app.rs" },
+                "region": { "startLine": 2, "startColumn": 5, "endLine": 4, "endColumn": 6 },
+            })
+        );
+        assert_eq!(results[1]["ruleId"], "error");
+        assert_eq!(
+            results[1]["locations"][0]["physicalLocation"]["region"],
+            json!({ "startLine": 3, "startColumn": 9 })
+        );
+        assert_eq!(results[2]["message"]["text"], "permission denied");
+        assert_eq!(
+            results[2]["locations"][0]["physicalLocation"],
+            json!({ "artifactLocation": { "uri": "src/unreadable" } })
+        );
+    }
+
+    #[test]
+    fn checkstyle_report() {
+        let output = render(MessageFormat::Checkstyle, &reports());
+        assert_eq!(
+            output,
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<checkstyle version="4.3">
+  <file name="src/app.rs">
+    <error line="2" column="5" severity="warning" message="view! macro is not formatted" source="leptosfmt.unformatted" />
+  </file>
+  <file name="src/lib.rs">
+  </file>
+  <file name="src/invalid.rs">
+    <error line="3" column="9" severity="error" message="could not parse view! macro: expected `&lt;`" source="leptosfmt.error" />
+  </file>
+  <file name="src/unreadable">
+    <error line="0" column="0" severity="error" message="permission denied" source="leptosfmt.error" />
+  </file>
+</checkstyle>
+"#
+        );
+    }
+}
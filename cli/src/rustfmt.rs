@@ -0,0 +1,135 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+};
+
+use anyhow::{bail, Context};
+use toml::Value;
+
+/// Formats `source` with rustfmt, as if it was a file located in `dir`.
+///
+/// rustfmt is started from `dir` so it picks up the `rustfmt.toml` of the project,
+/// and the edition is taken from the nearest `Cargo.toml`, like `cargo fmt` does.
+pub fn rustfmt(source: &str, dir: &Path) -> anyhow::Result<String> {
+    let rustfmt = env::var_os("RUSTFMT").unwrap_or_else(|| OsString::from("rustfmt"));
+
+    let mut command = Command::new(&rustfmt);
+    command.args(["--emit", "stdout"]);
+    if let Some(edition) = find_edition(dir) {
+        command.args(["--edition", &edition]);
+    }
+
+    let mut child = command
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("could not run {}", rustfmt.to_string_lossy()))?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = source.to_owned();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let output = child.wait_with_output()?;
+    writer.join().expect("rustfmt stdin writer panicked")?;
+
+    if !output.status.success() {
+        bail!(
+            "rustfmt failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Finds the edition of the package that contains `dir`, resolving `edition.workspace = true`
+fn find_edition(dir: &Path) -> Option<String> {
+    let mut inherits_edition = false;
+
+    for dir in dir.ancestors() {
+        let Some(manifest) = read_manifest(&dir.join("Cargo.toml")) else {
+            continue;
+        };
+
+        if inherits_edition {
+            let workspace_package = manifest.get("workspace").and_then(|w| w.get("package"));
+            if let Some(edition) = workspace_package.and_then(|p| p.get("edition")) {
+                return edition.as_str().map(str::to_owned);
+            }
+            continue;
+        }
+
+        let package = manifest.get("package")?;
+        match package.get("edition") {
+            Some(Value::String(edition)) => return Some(edition.clone()),
+            Some(Value::Table(edition)) if edition.contains_key("workspace") => {
+                inherits_edition = true
+            }
+            _ => return None,
+        }
+    }
+
+    None
+}
+
+fn read_manifest(path: &Path) -> Option<Value> {
+    let manifest = fs::read_to_string(path).ok()?;
+    toml::from_str(&manifest).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifests(manifests: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, contents) in manifests {
+            let path = dir.path().join(path);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("Cargo.toml"), contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn package_edition() {
+        let dir = manifests(&[("app", "[package]\nname = \"app\"\nedition = \"2021\"")]);
+        fs::create_dir(dir.path().join("app/src")).unwrap();
+        assert_eq!(
+            find_edition(&dir.path().join("app/src")),
+            Some("2021".to_owned())
+        );
+    }
+
+    #[test]
+    fn workspace_edition() {
+        let dir = manifests(&[
+            (
+                "",
+                "[workspace]\nmembers = [\"app\"]\n[workspace.package]\nedition = \"2018\"",
+            ),
+            ("app", "[package]\nname = \"app\"\nedition.workspace = true"),
+        ]);
+        assert_eq!(
+            find_edition(&dir.path().join("app")),
+            Some("2018".to_owned())
+        );
+    }
+
+    #[test]
+    fn no_edition() {
+        let dir = manifests(&[
+            ("", "[package]\nname = \"outer\"\nedition = \"2021\""),
+            ("app", "[package]\nname = \"app\""),
+        ]);
+        // the closest package decides, even when an outer package has an edition
+        assert_eq!(find_edition(&dir.path().join("app")), None);
+    }
+}
@@ -0,0 +1,78 @@
+use leptosfmt_formatter::{AttributeValueBraceStyle, FormatterSettings};
+use serde_json::{json, Value};
+
+use crate::config::RANGES;
+
+/// A JSON Schema of `leptosfmt.toml`, so editors can complete and check it, e.g. with taplo
+pub fn schema() -> Value {
+    let defaults = FormatterSettings::default();
+    let range = |key: &str| {
+        let (_, range) = RANGES.iter().find(|(name, _)| *name == key).unwrap();
+        (*range.start(), *range.end())
+    };
+    let (min_width, max_width) = range("max_width");
+    let (min_tab_spaces, max_tab_spaces) = range("tab_spaces");
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "leptosfmt.toml",
+        "description": "Configuration of leptosfmt, the formatter of the view! macros of Leptos",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "max_width": {
+                "description": "Maximum width of each line",
+                "type": "integer",
+                "minimum": min_width,
+                "maximum": max_width,
+                "default": defaults.max_width,
+            },
+            "tab_spaces": {
+                "description": "Number of spaces per indentation level",
+                "type": "integer",
+                "minimum": min_tab_spaces,
+                "maximum": max_tab_spaces,
+                "default": defaults.tab_spaces,
+            },
+            "attr_value_brace_style": {
+                "description": "Whether to add braces around single expression attribute values",
+                "type": "string",
+                "enum": [
+                    AttributeValueBraceStyle::Always,
+                    AttributeValueBraceStyle::AlwaysUnlessLit,
+                    AttributeValueBraceStyle::WhenRequired,
+                    AttributeValueBraceStyle::Preserve,
+                ],
+                "default": defaults.attr_value_brace_style,
+            },
+            "ignore": {
+                "description": "Gitignore-style patterns of files that should not be formatted, relative to this file",
+                "type": "array",
+                "items": { "type": "string" },
+                "default": [],
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn schema_has_every_key() {
+        let schema = schema();
+        let mut properties: Vec<_> = schema["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        properties.sort();
+
+        let mut keys = Config::keys();
+        keys.sort();
+        assert_eq!(properties, keys);
+    }
+}
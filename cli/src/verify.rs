@@ -0,0 +1,110 @@
+use std::ops::Range;
+
+use anyhow::Context;
+
+use leptosfmt_formatter::{format_file_edits, FormatterSettings, TextEdit};
+
+use crate::{diagnostic::Diagnostic, report::Region};
+
+/// Formats `formatted` a second time and fails if any of the view macros changed by `edits`,
+/// the edits that produced `formatted`, would change again
+pub fn verify_stable(
+    formatted: &str,
+    edits: &[TextEdit],
+    settings: FormatterSettings,
+) -> anyhow::Result<()> {
+    let formatted_ranges = edited_ranges(edits);
+    let unstable = format_file_edits(formatted, settings)
+        .context("could not parse the formatted source again")?
+        .into_iter()
+        .find(|edit| {
+            formatted_ranges.contains(&edit.range) && formatted[edit.range.clone()] != edit.new_text
+        });
+
+    let Some(unstable) = unstable else {
+        return Ok(());
+    };
+
+    let region = Region::of_range(formatted, unstable.range);
+    let message = "formatting is not stable";
+    let label = "formatting the formatted view! macro again changes it";
+    Err(
+        match Diagnostic::new(message, label, formatted, region.start, region.end) {
+            Some(diagnostic) => diagnostic.into(),
+            None => anyhow::anyhow!("{message}"),
+        },
+    )
+}
+
+/// The ranges of the new texts after applying `edits`, which are sorted by their position
+fn edited_ranges(edits: &[TextEdit]) -> Vec<Range<usize>> {
+    let mut offset: isize = 0;
+    edits
+        .iter()
+        .map(|edit| {
+            let start = (edit.range.start as isize + offset) as usize;
+            offset += edit.new_text.len() as isize - edit.range.len() as isize;
+            start..start + edit.new_text.len()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use leptosfmt_formatter::apply_edits;
+
+    use super::*;
+
+    fn edit(range: Range<usize>, new_text: &str) -> TextEdit {
+        TextEdit {
+            range,
+            new_text: new_text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn ranges_of_growing_and_shrinking_edits() {
+        let source = "aaa bbb ccc ddd";
+        let edits = vec![
+            edit(0..3, "AAAAA"),
+            edit(4..7, "B"),
+            edit(8..11, ""),
+            edit(12..15, "DDDD"),
+        ];
+        let ranges = edited_ranges(&edits);
+        assert_eq!(ranges, vec![0..5, 6..7, 8..8, 9..13]);
+
+        let formatted = apply_edits(source, edits.clone());
+        assert_eq!(formatted, "AAAAA B  DDDD");
+        for (range, edit) in ranges.into_iter().zip(&edits) {
+            assert_eq!(formatted[range], edit.new_text);
+        }
+    }
+
+    #[test]
+    fn formatted_source_is_stable() {
+        let source = r#"fn main() { view! { cx, <div   class="a">"hello"</div> }; }"#;
+        let settings = FormatterSettings::default();
+        let edits = format_file_edits(source, settings).unwrap();
+        let formatted = apply_edits(source, edits.clone());
+        assert!(verify_stable(&formatted, &edits, settings).is_ok());
+    }
+
+    #[test]
+    fn unstable_macro() {
+        let source = r#"fn main() { view! { cx, <div   class="a">"hello"</div> }; }"#;
+        let settings = FormatterSettings::default();
+
+        // pretend the edits left the unformatted macro as it is, so formatting it again changes it
+        let edits: Vec<_> = format_file_edits(source, settings)
+            .unwrap()
+            .into_iter()
+            .map(|formatted| edit(formatted.range.clone(), &source[formatted.range]))
+            .collect();
+        let err = verify_stable(source, &edits, settings).unwrap_err();
+        assert!(format!("{err:#}").contains("formatting is not stable"));
+
+        // unformatted macros that weren't edited are not checked
+        assert!(verify_stable(source, &[], settings).is_ok());
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, SystemTime},
+};
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+/// Time without new events after which the changed files are formatted
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches `dirs` recursively and calls `on_change` with the rust files that were saved.
+///
+/// Files that weren't modified since the last call of `on_change` are skipped,
+/// so formatting a file doesn't trigger another round of formatting.
+pub fn watch(dirs: &[PathBuf], mut on_change: impl FnMut(Vec<PathBuf>)) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::Recursive)?;
+    }
+
+    let mut handled = Handled::default();
+    loop {
+        // block until something happens, then wait until the events settle
+        let mut changed = BTreeSet::new();
+        collect_rust_files(rx.recv()?, &mut changed);
+        loop {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(event) => collect_rust_files(event, &mut changed),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+
+        let changed = handled.modified(changed);
+        if changed.is_empty() {
+            continue;
+        }
+
+        on_change(changed.clone());
+        handled.insert(changed);
+    }
+}
+
+/// The modification times of the files when they were last handled, so writing the
+/// formatted files doesn't trigger another round of formatting
+#[derive(Default)]
+struct Handled(HashMap<PathBuf, SystemTime>);
+
+impl Handled {
+    /// The files that exist and were modified since they were last handled
+    fn modified(&self, paths: impl IntoIterator<Item = PathBuf>) -> Vec<PathBuf> {
+        paths
+            .into_iter()
+            .filter(|path| {
+                let modified = modified(path);
+                modified.is_some() && modified != self.0.get(path).copied()
+            })
+            .collect()
+    }
+
+    /// Remembers the current modification times of `paths`, after they were handled
+    fn insert(&mut self, paths: Vec<PathBuf>) {
+        for path in paths {
+            if let Some(modified) = modified(&path) {
+                self.0.insert(path, modified);
+            }
+        }
+    }
+}
+
+fn collect_rust_files(event: notify::Result<Event>, changed: &mut BTreeSet<PathBuf>) {
+    let Ok(event) = event else {
+        return;
+    };
+
+    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+        return;
+    }
+
+    changed.extend(
+        event
+            .paths
+            .into_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rs")),
+    );
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    #[test]
+    fn skips_handled_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.rs"), dir.path().join("b.rs"));
+        fs::write(&a, "").unwrap();
+        let missing = dir.path().join("missing.rs");
+
+        let mut handled = Handled::default();
+        assert_eq!(handled.modified([a.clone(), missing]), vec![a.clone()]);
+
+        // formatting writes the file, which must not trigger another round
+        fs::write(&a, "fn main() {}").unwrap();
+        handled.insert(vec![a.clone()]);
+        assert!(handled.modified([a.clone()]).is_empty());
+
+        fs::write(&b, "").unwrap();
+        assert_eq!(handled.modified([a.clone(), b.clone()]), vec![b]);
+
+        // saving the file again
+        let later = modified(&a).unwrap() + Duration::from_secs(1);
+        File::options()
+            .write(true)
+            .open(&a)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(handled.modified([a.clone()]), vec![a]);
+    }
+}
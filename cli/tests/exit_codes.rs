@@ -0,0 +1,251 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+    time::{Duration, SystemTime},
+};
+
+use tempfile::TempDir;
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// Copies the fixtures to a temporary directory, so formatting them doesn't change the originals
+fn fixtures(names: &[&str]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let fixtures = fixtures_dir();
+    for name in names {
+        fs::copy(fixtures.join(name), dir.path().join(name)).unwrap();
+    }
+    dir
+}
+
+fn leptosfmt(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_leptosfmt"))
+        .current_dir(dir)
+        .arg("--no-cache")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn leptosfmt_stdin(dir: &Path, args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_leptosfmt"))
+        .current_dir(dir)
+        .arg("--no-cache")
+        .arg("--stdin")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn read(dir: &Path, name: &str) -> String {
+    fs::read_to_string(dir.join(name)).unwrap()
+}
+
+#[test]
+fn check_formatted_file() {
+    let dir = fixtures(&["formatted.rs"]);
+    let output = leptosfmt(dir.path(), &["--check", "formatted.rs"]);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn check_unformatted_file() {
+    let dir = fixtures(&["formatted.rs", "unformatted.rs"]);
+    let output = leptosfmt(dir.path(), &["--check", "."]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("unformatted.rs"));
+
+    let fixtures = fixtures_dir();
+    assert_eq!(
+        read(dir.path(), "unformatted.rs"),
+        read(&fixtures, "unformatted.rs")
+    );
+}
+
+#[test]
+fn check_invalid_file() {
+    let dir = fixtures(&["unformatted.rs", "invalid.rs"]);
+    let output = leptosfmt(dir.path(), &["--check", "."]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn format_files() {
+    let dir = fixtures(&["formatted.rs", "unformatted.rs"]);
+    let output = leptosfmt(dir.path(), &["."]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        read(dir.path(), "unformatted.rs"),
+        read(dir.path(), "formatted.rs")
+    );
+}
+
+#[test]
+fn format_invalid_file() {
+    let dir = fixtures(&["unformatted.rs", "invalid.rs"]);
+    let output = leptosfmt(dir.path(), &["."]);
+    assert_eq!(output.status.code(), Some(2));
+
+    // the other files are still formatted
+    let fixtures = fixtures_dir();
+    assert_eq!(
+        read(dir.path(), "unformatted.rs"),
+        read(&fixtures, "formatted.rs")
+    );
+    assert_eq!(
+        read(dir.path(), "invalid.rs"),
+        read(&fixtures, "invalid.rs")
+    );
+}
+
+#[test]
+fn one_job_reports_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let unformatted = fs::read_to_string(fixtures_dir().join("unformatted.rs")).unwrap();
+    let names = ["a/b.rs", "a/z.rs", "b.rs", "m1.rs", "m10.rs", "m2.rs"];
+    for name in names.iter().rev() {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, &unformatted).unwrap();
+    }
+
+    let output = leptosfmt(dir.path(), &["--jobs", "1", "--check", "."]);
+    assert_eq!(output.status.code(), Some(1));
+    let reported: Vec<_> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix("⚠️ "))
+        .map(|path| path.trim_start_matches("./").replace('\\', "/"))
+        .collect();
+    assert_eq!(reported, names);
+}
+
+#[test]
+fn format_stdin() {
+    let fixtures = fixtures_dir();
+    let output = leptosfmt_stdin(&fixtures, &[], &read(&fixtures, "unformatted.rs"));
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        read(&fixtures, "formatted.rs")
+    );
+}
+
+#[test]
+fn stdin_filepath_finds_config() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("crates/app")).unwrap();
+    fs::write(
+        dir.path().join("crates/app/leptosfmt.toml"),
+        "max_width = 20\n",
+    )
+    .unwrap();
+
+    let fixtures = fixtures_dir();
+    let output = leptosfmt_stdin(
+        dir.path(),
+        &["--stdin-filepath", "crates/app/src/main.rs"],
+        &read(&fixtures, "unformatted.rs"),
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "fn main() {\n    view! { cx,\n        <div>\"hello\"</div>\n    };\n}\n"
+    );
+}
+
+#[test]
+fn check_stdin() {
+    let fixtures = fixtures_dir();
+    let output = leptosfmt_stdin(&fixtures, &["--check"], &read(&fixtures, "formatted.rs"));
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+
+    let output = leptosfmt_stdin(&fixtures, &["--check"], &read(&fixtures, "unformatted.rs"));
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn unchanged_file_keeps_its_mtime() {
+    let dir = fixtures(&["formatted.rs", "unformatted.rs"]);
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    for name in ["formatted.rs", "unformatted.rs"] {
+        let file = File::options()
+            .write(true)
+            .open(dir.path().join(name))
+            .unwrap();
+        file.set_modified(modified).unwrap();
+    }
+
+    let output = leptosfmt(dir.path(), &["."]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let mtime = |name| {
+        let metadata = fs::metadata(dir.path().join(name)).unwrap();
+        metadata.modified().unwrap()
+    };
+    assert_eq!(mtime("formatted.rs"), modified);
+    assert_ne!(mtime("unformatted.rs"), modified);
+}
+
+#[cfg(unix)]
+#[test]
+fn rewrite_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = fixtures(&["unformatted.rs"]);
+    let path = dir.path().join("unformatted.rs");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o754)).unwrap();
+
+    let output = leptosfmt(dir.path(), &["unformatted.rs"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        read(dir.path(), "unformatted.rs"),
+        read(&fixtures_dir(), "formatted.rs")
+    );
+
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o754);
+    // the temporary file is renamed over the file
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn rustfmt_is_covered_by_the_timeout() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = fixtures(&["unformatted.rs"]);
+    let rustfmt = dir.path().join("rustfmt.sh");
+    fs::write(&rustfmt, "#!/bin/sh\nsleep 5\n").unwrap();
+    fs::set_permissions(&rustfmt, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_leptosfmt"))
+        .current_dir(dir.path())
+        .env("RUSTFMT", &rustfmt)
+        .args([
+            "--no-cache",
+            "--rustfmt",
+            "--timeout",
+            "1",
+            "unformatted.rs",
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("formatting took longer than 1s"));
+}
@@ -0,0 +1,3 @@
+fn main() {
+    view! { cx, <div>"hello"</div> };
+}
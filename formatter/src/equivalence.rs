@@ -0,0 +1,440 @@
+use std::collections::HashMap;
+
+use crop::Rope;
+use proc_macro2::{Delimiter, Spacing, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{spanned::Spanned, Macro};
+use syn_rsx::Node;
+
+use crate::{
+    collect::collect_macros_in_file,
+    source_file::{byte_offset, macro_span, FormatError, TextEdit},
+};
+
+/// A token that is compared between the original and the formatted view macro
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open(Delimiter),
+    Close(Delimiter),
+    Ident(String),
+    Punct(char),
+    Literal(String),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Open(Delimiter::Parenthesis) => "`(`".to_owned(),
+            Token::Open(Delimiter::Brace) => "`{`".to_owned(),
+            Token::Open(_) => "`[`".to_owned(),
+            Token::Close(Delimiter::Parenthesis) => "`)`".to_owned(),
+            Token::Close(Delimiter::Brace) => "`}`".to_owned(),
+            Token::Close(_) => "`]`".to_owned(),
+            Token::Ident(ident) => format!("`{ident}`"),
+            Token::Punct(punct) => format!("`{punct}`"),
+            // keep the description on one line, so it can be shown next to the source
+            Token::Literal(literal) => format!("`{}`", literal.replace('\n', "\\n")),
+        }
+    }
+}
+
+/// Checks that the edits of the view macros in `source` only change their formatting.
+///
+/// The nodes of every edited macro are compared with the nodes of the original macro, token by token.
+/// Whitespace and trailing commas are ignored, and so are the braces that formatting legitimately
+/// adds or removes: around attribute values and blocks, and around the body of a closure or match arm. Elements are compared as if
+/// they have a closing tag, as the formatter closes elements without children depending on their name.
+pub fn check_edits(source: &str, edits: &[TextEdit]) -> Result<(), FormatError> {
+    let edits: HashMap<_, _> = edits
+        .iter()
+        .map(|edit| (edit.range.clone(), &edit.new_text))
+        .collect();
+
+    let ast = syn::parse_file(source)?;
+    let rope: Rope = source.parse().unwrap();
+    for mac in collect_macros_in_file(&ast) {
+        let (start, end) = macro_span(mac);
+        let range = byte_offset(&rope, start)..byte_offset(&rope, end);
+        match edits.get(&range) {
+            Some(new_text) if source[range] != ***new_text => check_macro(mac, new_text)?,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn check_macro(original: &Macro, new_text: &str) -> Result<(), FormatError> {
+    let span = original.path.span();
+    let invalid = |err| {
+        let message = format!("the formatted view! macro is not valid: {err}");
+        FormatError::ChangedTokens(syn::Error::new(span, message))
+    };
+
+    let formatted: Macro = syn::parse_str(new_text).map_err(invalid)?;
+
+    let mut original_tokens = Vec::new();
+    macro_tokens(original.tokens.clone(), span, &mut original_tokens)
+        .map_err(|err| FormatError::view_macro(err, original))?;
+    let mut formatted_tokens = Vec::new();
+    macro_tokens(formatted.tokens, span, &mut formatted_tokens).map_err(invalid)?;
+
+    let mut original_iter = original_tokens.into_iter();
+    let mut formatted_iter = formatted_tokens.into_iter().map(|(token, _)| token);
+    loop {
+        let (expected, found) = match (original_iter.next(), formatted_iter.next()) {
+            (None, None) => return Ok(()),
+            (Some((expected, _)), Some(found)) if expected == found => continue,
+            (expected, found) => (expected, found),
+        };
+
+        let span = expected.as_ref().map_or(span, |(_, span)| *span);
+        let expected = expected.map_or("the end of the macro".to_owned(), |(token, _)| {
+            token.describe()
+        });
+        let found = found.map_or("the end of the macro".to_owned(), |token| token.describe());
+        let message = format!("formatting changes {expected} into {found}");
+        return Err(FormatError::ChangedTokens(syn::Error::new(span, message)));
+    }
+}
+
+/// Flattens the tokens of the body of a view macro, of which the nodes after `cx,` are parsed
+fn macro_tokens(
+    stream: TokenStream,
+    span: Span,
+    tokens: &mut Vec<(Token, Span)>,
+) -> syn::Result<()> {
+    let mut trees = stream.clone().into_iter();
+    let (Some(cx), Some(comma)) = (trees.next(), trees.next()) else {
+        flatten(stream, tokens);
+        return Ok(());
+    };
+
+    flatten(TokenStream::from_iter([cx, comma]), tokens);
+    for node in syn_rsx::parse2(trees.collect())? {
+        node_tokens(&node, span, tokens);
+    }
+    Ok(())
+}
+
+fn node_tokens(node: &Node, span: Span, tokens: &mut Vec<(Token, Span)>) {
+    match node {
+        Node::Element(element) => {
+            let span = element.name.span();
+            let name = element.name.to_token_stream();
+
+            tokens.push((Token::Punct('<'), span));
+            flatten(name.clone(), tokens);
+            for attribute in &element.attributes {
+                node_tokens(attribute, span, tokens);
+            }
+            tokens.push((Token::Punct('>'), span));
+
+            for child in &element.children {
+                node_tokens(child, span, tokens);
+            }
+
+            tokens.push((Token::Punct('<'), span));
+            tokens.push((Token::Punct('/'), span));
+            flatten(name, tokens);
+            tokens.push((Token::Punct('>'), span));
+        }
+        Node::Attribute(attribute) => {
+            flatten(attribute.key.to_token_stream(), tokens);
+            if let Some(value) = &attribute.value {
+                tokens.push((Token::Punct('='), attribute.key.span()));
+                flatten_unwrapped(value.as_ref().to_token_stream(), tokens);
+            }
+        }
+        Node::Text(text) => flatten(text.value.as_ref().to_token_stream(), tokens),
+        Node::Comment(comment) => {
+            tokens.push((Token::Punct('!'), span));
+            flatten(comment.value.as_ref().to_token_stream(), tokens);
+        }
+        Node::Doctype(doctype) => {
+            tokens.push((Token::Punct('!'), span));
+            tokens.push((Token::Ident("DOCTYPE".to_owned()), span));
+            flatten(doctype.value.as_ref().to_token_stream(), tokens);
+        }
+        Node::Block(block) => flatten_unwrapped(block.value.as_ref().to_token_stream(), tokens),
+        Node::Fragment(fragment) => {
+            tokens.push((Token::Punct('<'), span));
+            tokens.push((Token::Punct('>'), span));
+            for child in &fragment.children {
+                node_tokens(child, span, tokens);
+            }
+            tokens.push((Token::Punct('<'), span));
+            tokens.push((Token::Punct('/'), span));
+            tokens.push((Token::Punct('>'), span));
+        }
+    }
+}
+
+/// Flattens a token stream into the tokens that have to stay the same, skipping trailing commas.
+/// prettyplease wraps the body of a closure or match arm in a block when it spans multiple lines,
+/// and drops the comma after the block of a match arm, so the braces of those blocks and the commas
+/// that end a match arm are skipped as well.
+/// The bodies of nested view macros are flattened like the body of the macro that is checked.
+fn flatten(stream: TokenStream, tokens: &mut Vec<(Token, Span)>) {
+    let trees: Vec<_> = stream.into_iter().collect();
+    for (index, tree) in trees.iter().enumerate() {
+        match tree {
+            TokenTree::Group(group) if is_view_macro_body(&trees, index) => {
+                let mut body = Vec::new();
+                match macro_tokens(group.stream(), group.span(), &mut body) {
+                    Ok(()) => tokens.extend(body),
+                    Err(_) => flatten(group.stream(), tokens),
+                }
+            }
+            TokenTree::Group(group) => match group.delimiter() {
+                Delimiter::None => flatten(group.stream(), tokens),
+                Delimiter::Brace if is_closure_or_arm_body(&trees, index) => {
+                    flatten_body(group.stream(), tokens)
+                }
+                delimiter => {
+                    tokens.push((Token::Open(delimiter), group.span_open()));
+                    flatten(group.stream(), tokens);
+                    tokens.push((Token::Close(delimiter), group.span_close()));
+                }
+            },
+            TokenTree::Punct(punct) if punct.as_char() == ',' => {
+                let is_last = index + 1 == trees.len();
+                if !(is_last || ends_match_arm(&trees, index)) {
+                    tokens.push((Token::Punct(','), punct.span()));
+                }
+            }
+            TokenTree::Punct(punct) => tokens.push((Token::Punct(punct.as_char()), punct.span())),
+            TokenTree::Ident(ident) => tokens.push((Token::Ident(ident.to_string()), ident.span())),
+            TokenTree::Literal(literal) => {
+                tokens.push((Token::Literal(literal.to_string()), literal.span()))
+            }
+        }
+    }
+}
+
+/// Flattens an attribute value or block node without its braces, which the formatter adds or
+/// removes depending on `AttributeValueBraceStyle`
+fn flatten_unwrapped(stream: TokenStream, tokens: &mut Vec<(Token, Span)>) {
+    let mut trees = stream.clone().into_iter();
+    match (trees.next(), trees.next()) {
+        (Some(TokenTree::Group(group)), None) if group.delimiter() == Delimiter::Brace => {
+            flatten(group.stream(), tokens)
+        }
+        _ => flatten(stream, tokens),
+    }
+}
+
+/// Flattens the block of a closure or match arm body. prettyplease ends an assignment with a `;`
+/// when it wraps it in a block, so the `;` of a block with a single statement is skipped.
+fn flatten_body(stream: TokenStream, tokens: &mut Vec<(Token, Span)>) {
+    let mut trees: Vec<_> = stream.into_iter().collect();
+    let is_semicolon =
+        |tree: &TokenTree| matches!(tree, TokenTree::Punct(punct) if punct.as_char() == ';');
+    if trees.last().is_some_and(is_semicolon)
+        && trees.iter().filter(|t| is_semicolon(t)).count() == 1
+    {
+        trees.pop();
+    }
+    flatten(TokenStream::from_iter(trees), tokens)
+}
+
+/// Whether the group at `index` directly follows the parameters of a closure or the `=>` of a match arm
+fn is_closure_or_arm_body(trees: &[TokenTree], index: usize) -> bool {
+    index >= 1
+        && (matches!(&trees[index - 1], TokenTree::Punct(punct) if punct.as_char() == '|')
+            || is_fat_arrow(trees, index - 1))
+}
+
+/// Whether the comma at `index` separates two match arms, which is the last comma before the next `=>`
+fn ends_match_arm(trees: &[TokenTree], index: usize) -> bool {
+    let is_comma =
+        |tree: &TokenTree| matches!(tree, TokenTree::Punct(punct) if punct.as_char() == ',');
+    let follows_arm = (0..index).any(|i| is_fat_arrow(trees, i));
+    let next_arm = (index + 1..trees.len()).find(|&i| is_fat_arrow(trees, i));
+    follows_arm && next_arm.is_some_and(|next| !trees[index + 1..next].iter().any(is_comma))
+}
+
+/// Whether the token at `index` is the `>` of a `=>`
+fn is_fat_arrow(trees: &[TokenTree], index: usize) -> bool {
+    index >= 1
+        && matches!(&trees[index], TokenTree::Punct(punct) if punct.as_char() == '>')
+        && matches!(&trees[index - 1], TokenTree::Punct(punct) if punct.as_char() == '=' && punct.spacing() == Spacing::Joint)
+}
+
+/// Whether the group at `index` is the body of a `view!` macro
+fn is_view_macro_body(trees: &[TokenTree], index: usize) -> bool {
+    index >= 2
+        && matches!(&trees[index - 1], TokenTree::Punct(punct) if punct.as_char() == '!')
+        && matches!(&trees[index - 2], TokenTree::Ident(ident) if ident == "view")
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::{format_file_edits, AttributeValueBraceStyle, FormatterSettings};
+
+    fn check(source: &str, settings: FormatterSettings) -> Result<(), FormatError> {
+        let edits = format_file_edits(source, settings)?;
+        check_edits(source, &edits)
+    }
+
+    #[test]
+    fn formatting_is_equivalent() {
+        let source = indoc! {r#"
+            fn main() {
+                view! {   cx ,  <div class={"a"} on:click=move |_| { set_value(0) }>
+                    {move || if show() { view! { cx, <span/> } } else { view! { cx, <p>"b"</p> } }}
+                    <input type="text"></input>
+                    <Show when=move || a() fallback=|cx| view! { cx, <p>"loading"</p> }>"x"</Show>
+                    {items.iter().map(|item| match item { Item::A => 1, Item::B => { 2 }, }).collect::<Vec<_>>()}
+                </div>  };
+            }
+        "#};
+
+        check(source, Default::default()).unwrap();
+        check(
+            source,
+            FormatterSettings {
+                attr_value_brace_style: AttributeValueBraceStyle::Always,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    }
+
+    fn check_all_brace_styles(source: &str) {
+        for attr_value_brace_style in [
+            AttributeValueBraceStyle::Always,
+            AttributeValueBraceStyle::AlwaysUnlessLit,
+            AttributeValueBraceStyle::WhenRequired,
+            AttributeValueBraceStyle::Preserve,
+        ] {
+            let settings = FormatterSettings {
+                attr_value_brace_style,
+                ..Default::default()
+            };
+            check(source, settings).unwrap();
+        }
+    }
+
+    #[test]
+    fn match_arm_becomes_block() {
+        let source = indoc! {r#"
+            fn main() {
+                view! { cx,
+                    <div>
+                        {move || match count() { 0 => view!{cx, <span>"zero"</span>}.into_view(cx), _ => view!{cx, <span>"more"</span>}.into_view(cx) }}
+                    </div>
+                };
+            }
+        "#};
+
+        check_all_brace_styles(source);
+    }
+
+    #[test]
+    fn closure_body_becomes_block() {
+        let source = indoc! {r#"
+            fn main() {
+                view! { cx,
+                    <button on:click=move |_| set_value.update(|value| *value = some_really_long_function_name(value, another_argument_name))>
+                        {|| { let x = 1; x + 1 }}
+                    </button>
+                };
+            }
+        "#};
+
+        check_all_brace_styles(source);
+    }
+
+    #[test]
+    fn statement_moved_into_block() {
+        let source = indoc! {r#"
+            fn main() {
+                view! { cx, <button on:click=move |_| { if c { x(); } y(); }>"a"</button> };
+            }
+        "#};
+
+        let range = source.find("view!").unwrap()..source.find(" };").unwrap() + 2;
+        let edits = vec![TextEdit {
+            range: range.clone(),
+            new_text: source[range].replace("{ if c { x(); } y(); }", "{ if c { x(); y(); } }"),
+        }];
+        let err = check_edits(source, &edits).unwrap_err();
+        assert_eq!(
+            err.syn_error().unwrap().to_string(),
+            "formatting changes `}` into `y`"
+        );
+    }
+
+    fn flattened(source: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        flatten(source.parse().unwrap(), &mut tokens);
+        tokens.into_iter().map(|(token, _)| token).collect()
+    }
+
+    #[test]
+    fn only_commas_ending_match_arms_are_skipped() {
+        let commas = |source| {
+            flattened(source)
+                .into_iter()
+                .filter(|token| *token == Token::Punct(','))
+                .count()
+        };
+
+        // the comma between the parameters of the closure stays, the one ending the arm is skipped
+        assert_eq!(commas("x => |a, b| a + b, y => f(a, b), z => 1,"), 2);
+        assert_eq!(
+            flattened("x => { 1 } y => 2"),
+            flattened("x => 1, y => { 2 },")
+        );
+        // braces that don't wrap the body of a closure or match arm are kept
+        assert_ne!(flattened("if c { x } y"), flattened("if c { x y }"));
+    }
+
+    #[test]
+    fn changed_tokens() {
+        let source = indoc! {r#"
+            fn main() {
+                view! { cx, <div>"a"</div> };
+            }
+        "#};
+
+        let edits = vec![TextEdit {
+            range: 16..44,
+            new_text: r#"view! { cx, <div>"b"</div> }"#.to_owned(),
+        }];
+        assert_eq!(
+            &source[edits[0].range.clone()],
+            r#"view! { cx, <div>"a"</div> }"#
+        );
+
+        let err = check_edits(source, &edits).unwrap_err();
+        let syn_err = err.syn_error().unwrap();
+        assert_eq!(
+            syn_err.to_string(),
+            r#"formatting changes `"a"` into `"b"`"#
+        );
+        let start = syn_err.span().start();
+        assert_eq!((start.line, start.column), (2, 21));
+    }
+
+    #[test]
+    fn multi_line_string() {
+        let source = indoc! {r#"
+            fn main() {
+                view! { cx,
+                    <pre>"first
+                      second"</pre>
+                };
+            }
+        "#};
+
+        let err = check(source, Default::default()).unwrap_err();
+        assert!(matches!(err, FormatError::ChangedTokens(_)));
+        let start = err.syn_error().unwrap().span().start();
+        assert_eq!((start.line, start.column), (3, 13));
+    }
+}
@@ -59,7 +59,7 @@ impl Formatter {
         }
     }
 
-    pub fn children(&mut self, children: &Vec<Node>, attribute_count: usize) {
+    pub fn children(&mut self, children: &[Node], attribute_count: usize) {
         if children.is_empty() {
             return;
         }
@@ -4,18 +4,15 @@ use syn_rsx::Node;
 use super::{Formatter, FormatterSettings};
 
 impl Formatter {
-    pub fn view_macro(&mut self, mac: &Macro) {
+    pub fn view_macro(&mut self, mac: &Macro) -> syn::Result<()> {
         let mut tokens = mac.tokens.clone().into_iter();
-        let (Some(cx), Some(_comma)) = (tokens.next(), tokens.next()) else { return; };
+        let (Some(cx), Some(_comma)) = (tokens.next(), tokens.next()) else {
+            return Ok(());
+        };
         let span_start = mac.path.span().start();
         let indent = span_start.column as isize;
 
-        let nodes = syn_rsx::parse2(tokens.collect()).unwrap_or_else(|_| {
-            panic!(
-                "invalid rsx tokens at line: {}:{}",
-                span_start.line, span_start.column
-            )
-        });
+        let nodes = syn_rsx::parse2(tokens.collect())?;
 
         self.printer.cbox(indent);
         self.printer.word("view! { ");
@@ -25,6 +22,7 @@ impl Formatter {
         self.view_macro_nodes(nodes);
         self.printer.word("}");
         self.printer.end();
+        Ok(())
     }
 
     fn view_macro_nodes(&mut self, nodes: Vec<Node>) {
@@ -45,10 +43,10 @@ impl Formatter {
     }
 }
 
-pub fn format_macro(mac: &Macro, settings: FormatterSettings) -> String {
+pub fn format_macro(mac: &Macro, settings: FormatterSettings) -> syn::Result<String> {
     let mut formatter = Formatter::new(settings);
-    formatter.view_macro(mac);
-    formatter.printer.eof()
+    formatter.view_macro(mac)?;
+    Ok(formatter.printer.eof())
 }
 
 #[cfg(test)]
@@ -60,7 +58,7 @@ mod tests {
     macro_rules! view_macro {
         ($($tt:tt)*) => {{
             let mac: Macro = syn::parse2(quote! { $($tt)* }).unwrap();
-            format_macro(&mac, Default::default())
+            format_macro(&mac, Default::default()).unwrap()
         }}
     }
 
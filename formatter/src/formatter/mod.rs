@@ -8,9 +8,9 @@ mod mac;
 mod node;
 
 pub use mac::format_macro;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AttributeValueBraceStyle {
     Always,
     AlwaysUnlessLit,
@@ -18,8 +18,8 @@ pub enum AttributeValueBraceStyle {
     Preserve,
 }
 
-#[derive(Clone, Copy, Deserialize)]
-#[serde(default)] 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FormatterSettings {
     // Maximum width of each line
     pub max_width: usize,
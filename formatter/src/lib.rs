@@ -1,8 +1,7 @@
 use std::path::Path;
 
-use source_file::{format_file_source, FormatError};
-
 mod collect;
+mod equivalence;
 mod formatter;
 mod source_file;
 
@@ -10,7 +9,12 @@ mod source_file;
 mod test_helpers;
 
 pub use collect::collect_macros_in_file;
+pub use equivalence::check_edits;
 pub use formatter::*;
+pub use source_file::{
+    apply_edits, format_file_edits, format_file_edits_in_lines, format_file_source, FormatError,
+    TextEdit,
+};
 
 pub fn format_file(path: &Path, settings: FormatterSettings) -> Result<String, FormatError> {
     let file = std::fs::read_to_string(path)?;
@@ -1,6 +1,10 @@
-use std::{io, ops::Range};
+use std::{
+    io,
+    ops::{Range, RangeInclusive},
+};
 
 use crop::Rope;
+use proc_macro2::LineColumn;
 use syn::{parse_str, spanned::Spanned, Expr, Macro, MacroDelimiter};
 use thiserror::Error;
 
@@ -15,21 +19,79 @@ pub enum FormatError {
     IoError(#[from] io::Error),
     #[error("could not parse file")]
     ParseError(#[from] syn::Error),
+    #[error("could not parse view! macro")]
+    ViewMacroError(#[source] syn::Error),
+    #[error("formatting would change the code")]
+    ChangedTokens(#[source] syn::Error),
+}
+
+impl FormatError {
+    /// The syn error of an invalid file or view macro, which has the location of the error
+    pub fn syn_error(&self) -> Option<&syn::Error> {
+        match self {
+            FormatError::IoError(_) => None,
+            FormatError::ParseError(err)
+            | FormatError::ViewMacroError(err)
+            | FormatError::ChangedTokens(err) => Some(err),
+        }
+    }
+
+    /// An error in the body of the view macro `mac`. syn-rsx reports the end of the input with
+    /// the call site span, which is the start of the file, so such errors point at the macro instead.
+    pub(crate) fn view_macro(err: syn::Error, mac: &Macro) -> Self {
+        let start = err.span().start();
+        if (start.line, start.column) == (1, 0) {
+            FormatError::ViewMacroError(syn::Error::new_spanned(mac, err))
+        } else {
+            FormatError::ViewMacroError(err)
+        }
+    }
 }
 
-#[derive(Debug)]
-struct TextEdit {
-    range: Range<usize>,
-    new_text: String,
+/// A replacement of the text in `range` (a byte range of the original source) with `new_text`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub new_text: String,
 }
 
-pub(crate) fn format_file_source(
+pub fn format_file_source(
     source: &str,
     settings: FormatterSettings,
 ) -> Result<String, FormatError> {
+    let edits = format_file_edits(source, settings)?;
+    Ok(apply_edits(source, edits))
+}
+
+/// Formats the view macros in `source`, returning an edit for every macro instead of the formatted source
+pub fn format_file_edits(
+    source: &str,
+    settings: FormatterSettings,
+) -> Result<Vec<TextEdit>, FormatError> {
     let ast = syn::parse_file(source)?;
     let macros = collect_macros_in_file(&ast);
-    format_source(source, macros, settings)
+    macro_edits(source, macros, settings)
+}
+
+/// Like [`format_file_edits`], but only formats the view macros that intersect with
+/// one of the given line ranges. Lines are 1-based and the ranges are inclusive.
+pub fn format_file_edits_in_lines(
+    source: &str,
+    settings: FormatterSettings,
+    lines: &[RangeInclusive<usize>],
+) -> Result<Vec<TextEdit>, FormatError> {
+    let ast = syn::parse_file(source)?;
+    let macros = collect_macros_in_file(&ast)
+        .into_iter()
+        .filter(|mac| {
+            let (start, end) = macro_span(mac);
+            lines
+                .iter()
+                .any(|lines| *lines.start() <= end.line && start.line <= *lines.end())
+        })
+        .collect();
+
+    macro_edits(source, macros, settings)
 }
 
 pub(crate) fn format_expr_source(
@@ -38,34 +100,45 @@ pub(crate) fn format_expr_source(
 ) -> Result<String, FormatError> {
     let ast: Expr = parse_str(source)?;
     let macros = collect_macros_in_expr(&ast);
-    format_source(source, macros, settings)
+    let edits = macro_edits(source, macros, settings)?;
+    Ok(apply_edits(source, edits))
 }
 
-fn format_source<'a>(
+fn macro_edits<'a>(
     source: &'a str,
     macros: Vec<&'a Macro>,
     settings: FormatterSettings,
-) -> Result<String, FormatError> {
+) -> Result<Vec<TextEdit>, FormatError> {
+    let rope: Rope = source.parse().unwrap();
+
+    macros
+        .into_iter()
+        .map(|mac| {
+            let (start, end) = macro_span(mac);
+            Ok(TextEdit {
+                range: byte_offset(&rope, start)..byte_offset(&rope, end),
+                new_text: format_macro(mac, settings)
+                    .map_err(|err| FormatError::view_macro(err, mac))?,
+            })
+        })
+        .collect()
+}
+
+/// Start and end location of a macro invocation, from its path up to and including the closing delimiter
+pub(crate) fn macro_span(mac: &Macro) -> (LineColumn, LineColumn) {
+    let start = mac.path.span().start();
+    let end = match mac.delimiter {
+        MacroDelimiter::Paren(delim) => delim.span.end(),
+        MacroDelimiter::Brace(delim) => delim.span.end(),
+        MacroDelimiter::Bracket(delim) => delim.span.end(),
+    };
+
+    (start, end)
+}
+
+/// Applies non-overlapping edits, sorted by their position in `source`
+pub fn apply_edits(source: &str, edits: Vec<TextEdit>) -> String {
     let mut source: Rope = source.parse().unwrap();
-    let mut edits = Vec::new();
-
-    for mac in macros {
-        let start = mac.path.span().start();
-        let end = match mac.delimiter {
-            MacroDelimiter::Paren(delim) => delim.span.end(),
-            MacroDelimiter::Brace(delim) => delim.span.end(),
-            MacroDelimiter::Bracket(delim) => delim.span.end(),
-        };
-
-        let start_byte = source.byte_of_line(start.line - 1) + start.column;
-        let end_byte = source.byte_of_line(end.line - 1) + end.column;
-        let new_text = format_macro(mac, settings);
-
-        edits.push(TextEdit {
-            range: start_byte..end_byte,
-            new_text,
-        });
-    }
 
     let mut last_offset: isize = 0;
     for edit in edits {
@@ -80,7 +153,20 @@ fn format_source<'a>(
         last_offset += new_text.len() as isize - (end as isize - start as isize);
     }
 
-    Ok(source.to_string())
+    source.to_string()
+}
+
+/// Converts a span location, of which the column is counted in chars, to a byte offset
+pub(crate) fn byte_offset(source: &Rope, location: LineColumn) -> usize {
+    let line_start = source.byte_of_line(location.line - 1);
+    let column_bytes: usize = source
+        .line(location.line - 1)
+        .chars()
+        .take(location.column)
+        .map(char::len_utf8)
+        .sum();
+
+    line_start + column_bytes
 }
 
 #[cfg(test)]
@@ -166,4 +252,99 @@ mod tests {
         }
         "###);
     }
+
+    #[test]
+    fn non_ascii_before_macro() {
+        let source = indoc! {r#"
+            fn main() {
+                let a = "ö"; view! {   cx ,  <div>"ü"</div>  }; 
+            }
+        "#};
+
+        let result = format_file_source(source, Default::default()).unwrap();
+        insta::assert_snapshot!(result, @r###"
+        fn main() {
+            let a = "ö"; view! { cx, <div>"ü"</div> }; 
+        }
+        "###);
+    }
+
+    #[test]
+    fn edits() {
+        let source = indoc! {r#"
+            fn main() {
+                view! { cx, <div>"hello"</div> };
+                view! {   cx ,  <span>"hello"</span>  };
+            }
+        "#};
+
+        let edits = format_file_edits(source, Default::default()).unwrap();
+        assert_eq!(edits.len(), 2);
+        assert_eq!(&source[edits[0].range.clone()], edits[0].new_text);
+        assert_eq!(
+            &source[edits[1].range.clone()],
+            r#"view! {   cx ,  <span>"hello"</span>  }"#
+        );
+        assert_eq!(edits[1].new_text, r#"view! { cx, <span>"hello"</span> }"#);
+    }
+
+    #[test]
+    fn in_lines() {
+        let source = indoc! {r#"
+            fn main() {
+                view! {   cx ,  <div>"a"</div>  };
+                view! {   cx ,
+                    <div>"b"</div>  };
+                view! {   cx ,  <div>"c"</div>  };
+            }
+        "#};
+
+        let edits = format_file_edits_in_lines(source, Default::default(), &[4..=4]).unwrap();
+        let result = apply_edits(source, edits);
+        insta::assert_snapshot!(result, @r###"
+        fn main() {
+            view! {   cx ,  <div>"a"</div>  };
+            view! { cx, <div>"b"</div> };
+            view! {   cx ,  <div>"c"</div>  };
+        }
+        "###);
+    }
+
+    #[test]
+    fn invalid_view_macro() {
+        let source = indoc! {r#"
+            fn main() {
+                view! { cx,
+                    <div class=>"a"</div>
+                };
+            }
+        "#};
+
+        let err = format_file_source(source, Default::default()).unwrap_err();
+        assert!(matches!(err, FormatError::ViewMacroError(_)));
+
+        let syn_err = err.syn_error().unwrap();
+        assert_eq!(syn_err.to_string(), "missing attribute value");
+        let start = syn_err.span().start();
+        assert_eq!((start.line, start.column), (3, 13));
+    }
+
+    #[test]
+    fn view_macro_ends_early() {
+        let source = indoc! {r#"
+            fn main() {
+                let x = 1;
+                view! { cx,
+                    <div style:color=move || if a { "red" } else>"x"</div>
+                };
+            }
+        "#};
+
+        let err = format_file_source(source, Default::default()).unwrap_err();
+        let syn_err = err.syn_error().unwrap();
+        assert_eq!(syn_err.to_string(), "unexpected end of input");
+        let (start, end) = (syn_err.span().start(), syn_err.span().end());
+        assert_eq!((start.line, start.column), (3, 4));
+        assert_eq!((end.line, end.column), (5, 5));
+    }
 }
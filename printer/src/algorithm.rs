@@ -261,7 +261,7 @@ impl Printer {
 
     fn check_stack(&mut self, mut depth: usize) {
         while let Some(&index) = self.scan_stack.back() {
-            let mut entry = &mut self.buf[index];
+            let entry = &mut self.buf[index];
             match entry.token {
                 Token::Begin(_) => {
                     if depth == 0 {
@@ -353,7 +353,7 @@ impl Printer {
     fn print_indent(&mut self) {
         self.out.reserve(self.pending_indentation);
         self.out
-            .extend(iter::repeat(' ').take(self.pending_indentation));
+            .extend(iter::repeat_n(' ', self.pending_indentation));
         self.pending_indentation = 0;
     }
 }